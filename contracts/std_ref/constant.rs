@@ -2,3 +2,9 @@ pub const E9: u64 = 1_000_000_000u64;
 pub const E18: u128 = 1_000_000_000_000_000_000u128;
 
 pub const USD: &str = "USD";
+
+/// Version of the storage layout written by this code
+pub const STORAGE_VERSION: u32 = 1;
+
+/// Default delay in milliseconds before a scheduled admin operation can be executed
+pub const DEFAULT_TIMELOCK_DELAY: u64 = 2 * 24 * 60 * 60 * 1000;
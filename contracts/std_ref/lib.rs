@@ -2,17 +2,21 @@
 mod constant;
 mod ref_data;
 mod reference_data;
+mod relayer;
+mod timelock;
 
 #[ink::contract]
-mod std_ref {
+pub mod std_ref {
     use ink::env::set_code_hash;
     use ink::prelude::string::String;
     use ink::prelude::vec::Vec;
-    use ink::storage::Mapping;
+    use ink::storage::{Lazy, Mapping};
 
-    use crate::constant::{E9, USD};
+    use crate::constant::{DEFAULT_TIMELOCK_DELAY, E9, STORAGE_VERSION, USD};
     use crate::ref_data::RefDatum;
     use crate::reference_data::ReferenceData;
+    pub use crate::relayer::{RelayerInfo, RelayerStatus};
+    pub use crate::timelock::{Operation, OperationKind, PendingOperation};
 
     #[ink(storage)]
     pub struct StandardReference {
@@ -22,6 +26,18 @@ mod std_ref {
         relayers: Mapping<AccountId, ()>,
         /// Mapping from symbol to reference datum
         ref_data: Mapping<String, RefDatum>,
+        /// Version of the storage layout, unset for deployments predating versioning
+        storage_version: Lazy<u32>,
+        /// Delay in milliseconds before a scheduled admin operation can be executed
+        timelock_delay: Lazy<Timestamp>,
+        /// Mapping from operation kind to the scheduled admin operation
+        pending_operations: Mapping<OperationKind, PendingOperation>,
+        /// List of the granted relayers, used for enumerating `relayers`
+        relayer_list: Lazy<Vec<AccountId>>,
+        /// Mapping from relayer to its liveness information
+        relayer_info: Mapping<AccountId, RelayerInfo>,
+        /// Time in milliseconds without relaying after which a relayer is reported as stale
+        inactivity_threshold: Lazy<Timestamp>,
     }
 
     /// Errors that can occur in the contract
@@ -34,35 +50,134 @@ mod std_ref {
         InvalidValue,
         /// Returned if unauthorized caller tries to call a function that requires authorization.
         Unauthorized,
+        /// Returned if the code hash could not be set.
+        UpgradeFailed,
+        /// Returned if the storage is already at the current version.
+        AlreadyMigrated,
+        /// Returned if the storage must be migrated before the call can be made.
+        MigrationRequired,
+        /// Returned if an operation of the same kind is already scheduled.
+        OperationAlreadyPending,
+        /// Returned if no operation of the given kind is scheduled.
+        NoPendingOperation,
+        /// Returned if the timelock delay of the operation has not passed yet.
+        TimelockNotExpired,
     }
 
     pub type Result<T> = core::result::Result<T, Error>;
 
     impl StandardReference {
         /// Creates a new StandardReference Contract
+        ///
+        /// # Note
+        ///
+        /// The admin is not a relayer, relayers are only granted through
+        /// `schedule_add_relayers` once the timelock has passed.
         #[ink(constructor)]
         pub fn new(admin: AccountId) -> Self {
-            let mut relayers = Mapping::new();
-            relayers.insert(admin, &());
+            Self::new_with_timelock_delay(admin, DEFAULT_TIMELOCK_DELAY)
+        }
+
+        /// Creates a new StandardReference Contract with a custom timelock delay
+        #[ink(constructor)]
+        pub fn new_with_timelock_delay(admin: AccountId, timelock_delay: Timestamp) -> Self {
+            let relayers = Mapping::new();
 
             let ref_data = Mapping::new();
 
+            let mut storage_version = Lazy::new();
+            storage_version.set(&STORAGE_VERSION);
+
+            let mut delay = Lazy::new();
+            delay.set(&timelock_delay);
+
             Self {
                 admin,
                 ref_data,
                 relayers,
+                storage_version,
+                timelock_delay: delay,
+                pending_operations: Mapping::new(),
+                relayer_list: Lazy::new(),
+                relayer_info: Mapping::new(),
+                inactivity_threshold: Lazy::new(),
+            }
+        }
+
+        /// Schedules an upgrade of the StandardReference contract
+        #[ink(message)]
+        pub fn schedule_upgrade(&mut self, code_hash: [u8; 32]) -> Result<()> {
+            self.schedule(Operation::Upgrade(code_hash))
+        }
+
+        /// Upgrades the StandardReference contract once the timelock delay has passed
+        ///
+        /// # Note
+        ///
+        /// If the new code changes the storage layout, `migrate` must be called
+        /// right after the upgrade.
+        #[ink(message)]
+        pub fn execute_upgrade(&mut self) -> Result<()> {
+            match self.take_executable(OperationKind::Upgrade)? {
+                Operation::Upgrade(code_hash) => {
+                    set_code_hash(&code_hash).map_err(|_| Error::UpgradeFailed)
+                }
+                _ => unreachable!(),
             }
         }
 
-        /// Upgrades the StandardReference contract
+        /// Cancels the scheduled upgrade.
+        #[ink(message)]
+        pub fn cancel_upgrade(&mut self) -> Result<()> {
+            self.cancel(OperationKind::Upgrade)
+        }
+
+        /// Migrates the storage to the layout of the current code.
+        ///
+        /// Can only be run once per version. The new version is stored before
+        /// any migration step is run so that re-entrant calls are rejected.
         #[ink(message)]
-        pub fn upgrade(&mut self, code_hash: [u8; 32]) {
+        pub fn migrate(&mut self) -> Result<()> {
             if self.admin != self.env().caller() {
-                panic!("Unauthorized");
+                return Err(Error::Unauthorized);
+            }
+
+            let from_version = self.storage_version();
+            if from_version >= STORAGE_VERSION {
+                return Err(Error::AlreadyMigrated);
             }
+            self.storage_version.set(&STORAGE_VERSION);
 
-            set_code_hash(&code_hash)
-                .unwrap_or_else(|err| panic!("Failed to set code hash due to {:?}", err));
+            for version in from_version..STORAGE_VERSION {
+                self.migrate_from(version);
+            }
+
+            Ok(())
+        }
+
+        /// Migrates the storage from the given version to the next one.
+        fn migrate_from(&mut self, version: u32) {
+            match version {
+                // Deployments predating versioning share the layout of version 1,
+                // their relayers are listed afterwards with `backfill_relayers`
+                0 => {}
+                _ => unreachable!("no migration from storage version {}", version),
+            }
+        }
+
+        /// Returns the version of the storage layout.
+        #[ink(message)]
+        pub fn storage_version(&self) -> u32 {
+            self.storage_version.get().unwrap_or(0)
+        }
+
+        /// Checks that the storage has been migrated to the current version.
+        #[inline]
+        fn ensure_migrated(&self) -> Result<()> {
+            if self.storage_version() < STORAGE_VERSION {
+                return Err(Error::MigrationRequired);
+            }
+            Ok(())
         }
 
         /// Returns the account ID of the contract.
@@ -77,16 +192,128 @@ mod std_ref {
             self.admin
         }
 
-        /// Transfers the admin role to a new admin.
+        /// Schedules the transfer of the admin role to a new admin.
         #[ink(message)]
-        pub fn transfer_admin(&mut self, new_admin: AccountId) -> Result<()> {
+        pub fn schedule_transfer_admin(&mut self, new_admin: AccountId) -> Result<()> {
+            self.schedule(Operation::TransferAdmin(new_admin))
+        }
+
+        /// Transfers the admin role once the timelock delay has passed.
+        #[ink(message)]
+        pub fn execute_transfer_admin(&mut self) -> Result<()> {
+            match self.take_executable(OperationKind::TransferAdmin)? {
+                Operation::TransferAdmin(new_admin) => {
+                    self.admin = new_admin;
+                    Ok(())
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        /// Cancels the scheduled admin transfer.
+        #[ink(message)]
+        pub fn cancel_transfer_admin(&mut self) -> Result<()> {
+            self.cancel(OperationKind::TransferAdmin)
+        }
+
+        /// Returns the delay in milliseconds applied to scheduled admin operations.
+        #[ink(message)]
+        pub fn timelock_delay(&self) -> Timestamp {
+            self.timelock_delay.get().unwrap_or(DEFAULT_TIMELOCK_DELAY)
+        }
+
+        /// Schedules a change of the timelock delay.
+        #[ink(message)]
+        pub fn schedule_timelock_delay(&mut self, delay: Timestamp) -> Result<()> {
+            self.schedule(Operation::SetTimelockDelay(delay))
+        }
+
+        /// Changes the timelock delay once the current delay has passed.
+        #[ink(message)]
+        pub fn execute_timelock_delay(&mut self) -> Result<()> {
+            match self.take_executable(OperationKind::SetTimelockDelay)? {
+                Operation::SetTimelockDelay(delay) => {
+                    self.timelock_delay.set(&delay);
+                    Ok(())
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        /// Cancels the scheduled timelock delay change.
+        #[ink(message)]
+        pub fn cancel_timelock_delay(&mut self) -> Result<()> {
+            self.cancel(OperationKind::SetTimelockDelay)
+        }
+
+        /// Returns the scheduled operation of the given kind.
+        #[ink(message)]
+        pub fn pending_operation(&self, kind: OperationKind) -> Option<PendingOperation> {
+            self.pending_operations.get(kind)
+        }
+
+        /// Returns all scheduled operations.
+        #[ink(message)]
+        pub fn pending_operations(&self) -> Vec<PendingOperation> {
+            OperationKind::ALL
+                .iter()
+                .filter_map(|kind| self.pending_operations.get(kind))
+                .collect()
+        }
+
+        /// Schedules an admin operation to be executable after the timelock delay.
+        fn schedule(&mut self, operation: Operation) -> Result<()> {
             if self.admin != self.env().caller() {
                 return Err(Error::Unauthorized);
             }
-            self.admin = new_admin;
+
+            let kind = operation.kind();
+            if self.pending_operations.contains(kind) {
+                return Err(Error::OperationAlreadyPending);
+            }
+
+            let executable_at = self
+                .env()
+                .block_timestamp()
+                .checked_add(self.timelock_delay())
+                .ok_or(Error::InvalidValue)?;
+            self.pending_operations
+                .insert(kind, &PendingOperation::new(operation, executable_at));
+
             Ok(())
         }
 
+        /// Removes and returns the scheduled operation of the given kind if its
+        /// timelock delay has passed.
+        fn take_executable(&mut self, kind: OperationKind) -> Result<Operation> {
+            if self.admin != self.env().caller() {
+                return Err(Error::Unauthorized);
+            }
+
+            let pending = self
+                .pending_operations
+                .get(kind)
+                .ok_or(Error::NoPendingOperation)?;
+            if self.env().block_timestamp() < pending.executable_at {
+                return Err(Error::TimelockNotExpired);
+            }
+            self.pending_operations.remove(kind);
+
+            Ok(pending.operation)
+        }
+
+        /// Removes the scheduled operation of the given kind.
+        fn cancel(&mut self, kind: OperationKind) -> Result<()> {
+            if self.admin != self.env().caller() {
+                return Err(Error::Unauthorized);
+            }
+
+            self.pending_operations
+                .take(kind)
+                .map(|_| ())
+                .ok_or(Error::NoPendingOperation)
+        }
+
         /// Checks if caller is relayer.
         #[ink(message)]
         pub fn is_relayer(&self, relayer: AccountId) -> bool {
@@ -104,30 +331,160 @@ mod std_ref {
             self.relayers.contains(relayer)
         }
 
-        /// Adds relayers.
+        /// Schedules the addition of relayers.
+        ///
+        /// # Note
+        ///
+        /// Relayers can force relay any rate, so granting the role goes through
+        /// the timelock while revoking it with `remove_relayers` is immediate.
+        #[ink(message)]
+        pub fn schedule_add_relayers(&mut self, relayers: Vec<AccountId>) -> Result<()> {
+            self.schedule(Operation::AddRelayers(relayers))
+        }
+
+        /// Adds relayers once the timelock delay has passed.
         #[ink(message)]
-        pub fn add_relayers(&mut self, relayers: Vec<AccountId>) -> Result<()> {
+        pub fn execute_add_relayers(&mut self) -> Result<()> {
+            match self.take_executable(OperationKind::AddRelayers)? {
+                Operation::AddRelayers(relayers) => {
+                    for relayer in relayers {
+                        self.add_relayer_impl(relayer);
+                    }
+                    Ok(())
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        /// Cancels the scheduled relayer addition.
+        #[ink(message)]
+        pub fn cancel_add_relayers(&mut self) -> Result<()> {
+            self.cancel(OperationKind::AddRelayers)
+        }
+
+        /// Grants the relayer role and starts tracking its liveness.
+        fn add_relayer_impl(&mut self, relayer: AccountId) {
+            self.track_relayer(relayer);
+            self.relayers.insert(relayer, &());
+        }
+
+        /// Lists the relayer and starts tracking its liveness if not done yet.
+        fn track_relayer(&mut self, relayer: AccountId) {
+            let mut relayer_list = self.relayer_list.get_or_default();
+            if !relayer_list.contains(&relayer) {
+                relayer_list.push(relayer);
+                self.relayer_list.set(&relayer_list);
+            }
+            if !self.relayer_info.contains(relayer) {
+                self.relayer_info
+                    .insert(relayer, &RelayerInfo::new(self.env().block_timestamp()));
+            }
+        }
+
+        /// Removes relayers.
+        ///
+        /// Not timelocked, so that a compromised relayer can be revoked at once.
+        #[ink(message)]
+        pub fn remove_relayers(&mut self, relayers: Vec<AccountId>) -> Result<()> {
             if self.admin != self.env().caller() {
                 return Err(Error::Unauthorized);
             }
+
+            let mut relayer_list = self.relayer_list.get_or_default();
+            relayer_list.retain(|relayer| !relayers.contains(relayer));
+            self.relayer_list.set(&relayer_list);
+
             for relayer in relayers {
-                self.relayers.insert(relayer, &());
+                self.relayers.take(relayer);
+                self.relayer_info.take(relayer);
             }
             Ok(())
         }
 
-        /// Removes relayers.
+        /// Starts tracking the liveness of relayers granted before it existed.
+        ///
+        /// # Note
+        ///
+        /// `relayers` cannot be enumerated, so the admin lists them once after
+        /// upgrading a deployment predating liveness tracking. Nothing is
+        /// tracked if any of the given accounts is not a relayer.
         #[ink(message)]
-        pub fn remove_relayers(&mut self, relayers: Vec<AccountId>) -> Result<()> {
+        pub fn backfill_relayers(&mut self, relayers: Vec<AccountId>) -> Result<()> {
             if self.admin != self.env().caller() {
                 return Err(Error::Unauthorized);
             }
+            if !relayers.iter().all(|relayer| self.is_relayer_impl(relayer)) {
+                return Err(Error::InvalidValue);
+            }
+
             for relayer in relayers {
-                self.relayers.take(relayer);
+                self.track_relayer(relayer);
             }
             Ok(())
         }
 
+        /// Returns the liveness of a relayer or `None` if it is not a relayer.
+        #[ink(message)]
+        pub fn get_relayer_info(&self, relayer: AccountId) -> Option<RelayerStatus> {
+            if !self.is_relayer_impl(&relayer) {
+                return None;
+            }
+            Some(self.get_relayer_status(relayer))
+        }
+
+        /// Returns the liveness of all listed relayers.
+        #[ink(message)]
+        pub fn list_relayers(&self) -> Vec<RelayerStatus> {
+            self.relayer_list
+                .get_or_default()
+                .into_iter()
+                .map(|relayer| self.get_relayer_status(relayer))
+                .collect()
+        }
+
+        /// Returns the time in milliseconds without relaying after which a
+        /// relayer is reported as stale. Zero disables the check.
+        #[ink(message)]
+        pub fn inactivity_threshold(&self) -> Timestamp {
+            self.inactivity_threshold.get_or_default()
+        }
+
+        /// Sets the inactivity threshold of relayers.
+        #[ink(message)]
+        pub fn set_inactivity_threshold(&mut self, threshold: Timestamp) -> Result<()> {
+            if self.admin != self.env().caller() {
+                return Err(Error::Unauthorized);
+            }
+            self.inactivity_threshold.set(&threshold);
+            Ok(())
+        }
+
+        /// Returns the liveness of a relayer.
+        fn get_relayer_status(&self, relayer: AccountId) -> RelayerStatus {
+            let info = self.relayer_info.get(relayer).unwrap_or_default();
+            let threshold = self.inactivity_threshold();
+            let is_stale = threshold != 0
+                && self
+                    .env()
+                    .block_timestamp()
+                    .saturating_sub(info.last_active_time())
+                    > threshold;
+
+            RelayerStatus {
+                relayer,
+                info,
+                is_stale,
+            }
+        }
+
+        /// Records a relay made by the caller.
+        #[inline]
+        fn record_relay(&mut self, relayer: AccountId) {
+            let mut info = self.relayer_info.get(relayer).unwrap_or_default();
+            info.record_relay(self.env().block_number(), self.env().block_timestamp());
+            self.relayer_info.insert(relayer, &info);
+        }
+
         /// Returns the reference data for a given symbol
         #[ink(message)]
         pub fn get_reference_data(
@@ -170,9 +527,12 @@ mod std_ref {
             resolve_time: Timestamp,
             request_id: u64,
         ) -> Result<()> {
-            if !self.is_relayer_impl(&self.env().caller()) {
+            let caller = self.env().caller();
+            if !self.is_relayer_impl(&caller) {
                 return Err(Error::Unauthorized);
             }
+            self.ensure_migrated()?;
+            self.record_relay(caller);
 
             for (symbol, rate) in symbol_rates {
                 let ref_datum = match self.ref_data.get(&symbol) {
@@ -196,9 +556,12 @@ mod std_ref {
             resolve_time: Timestamp,
             request_id: u64,
         ) -> Result<()> {
-            if !self.is_relayer_impl(&self.env().caller()) {
+            let caller = self.env().caller();
+            if !self.is_relayer_impl(&caller) {
                 return Err(Error::Unauthorized);
             }
+            self.ensure_migrated()?;
+            self.record_relay(caller);
 
             for (symbol, rate) in symbol_rates {
                 self.ref_data
@@ -213,30 +576,57 @@ mod std_ref {
     mod tests {
         use super::*;
 
-        fn setup(admin: AccountId, relayers: &Vec<AccountId>) -> StandardReference {
-            let mut std_ref = StandardReference::new(admin);
-            let _ = std_ref.add_relayers(relayers.clone());
+        fn set_block_timestamp(timestamp: Timestamp) {
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(timestamp);
+        }
+
+        fn setup(admin: AccountId, relayers: &[AccountId]) -> StandardReference {
+            let mut std_ref = StandardReference::new_with_timelock_delay(admin, 0);
+            add_relayers(&mut std_ref, relayers);
             std_ref
         }
 
+        /// Adds relayers to a contract deployed without timelock delay.
+        fn add_relayers(std_ref: &mut StandardReference, relayers: &[AccountId]) {
+            assert_eq!(std_ref.schedule_add_relayers(relayers.to_vec()), Ok(()));
+            assert_eq!(std_ref.execute_add_relayers(), Ok(()));
+        }
+
         #[ink::test]
         fn test_init() {
             let admin = AccountId::from([0x01; 32]);
-            let std_ref = StandardReference::new(admin);
+            let mut std_ref = StandardReference::new(admin);
             assert_eq!(std_ref.current_admin(), admin);
+
+            // The admin cannot relay without being added as a relayer
+            assert!(!std_ref.is_relayer(admin));
+            assert_eq!(std_ref.list_relayers(), vec![]);
+            assert_eq!(
+                std_ref.relay(vec![("BTC".to_string(), E9)], 1, 1),
+                Err(Error::Unauthorized)
+            );
+            assert_eq!(
+                std_ref.force_relay(vec![("BTC".to_string(), E9)], 1, 1),
+                Err(Error::Unauthorized)
+            );
         }
 
         #[ink::test]
         fn test_transfer_admin() {
             let admin = AccountId::from([0x01; 32]);
             let new_admin = AccountId::from([0x02; 32]);
-            let relayer = AccountId::from([0x03; 32]);
 
             let mut std_ref = StandardReference::new(admin);
-            let _ = std_ref.add_relayers(vec![relayer]);
 
-            // Transfer admin role successfully
-            let result = std_ref.transfer_admin(new_admin);
+            // Transfer admin role successfully once the timelock has passed
+            assert_eq!(std_ref.schedule_transfer_admin(new_admin), Ok(()));
+            assert_eq!(
+                std_ref.execute_transfer_admin(),
+                Err(Error::TimelockNotExpired)
+            );
+
+            set_block_timestamp(DEFAULT_TIMELOCK_DELAY);
+            let result = std_ref.execute_transfer_admin();
             assert_eq!(result, Ok(()));
             assert_eq!(std_ref.current_admin(), new_admin);
         }
@@ -250,10 +640,37 @@ mod std_ref {
                 AccountId::from([0x03; 32]),
                 AccountId::from([0x04; 32]),
             ];
-            assert_eq!(std_ref.add_relayers(relayers.clone()), Ok(()));
+
+            // Relayers are only added once the timelock has passed
+            assert_eq!(std_ref.schedule_add_relayers(relayers.clone()), Ok(()));
+            assert_eq!(
+                std_ref.execute_add_relayers(),
+                Err(Error::TimelockNotExpired)
+            );
+            assert!(!std_ref.is_relayer(relayers[0]));
+
+            set_block_timestamp(DEFAULT_TIMELOCK_DELAY);
+            assert_eq!(std_ref.execute_add_relayers(), Ok(()));
             for relayer in relayers.clone() {
                 assert!(std_ref.is_relayer(relayer));
             }
+
+            // Scheduled additions can be cancelled
+            let relayer = AccountId::from([0x05; 32]);
+            assert_eq!(std_ref.schedule_add_relayers(vec![relayer]), Ok(()));
+            assert_eq!(std_ref.cancel_add_relayers(), Ok(()));
+            assert_eq!(
+                std_ref.execute_add_relayers(),
+                Err(Error::NoPendingOperation)
+            );
+            assert!(!std_ref.is_relayer(relayer));
+
+            // Only the admin can add relayers
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(relayers[0]);
+            assert_eq!(
+                std_ref.schedule_add_relayers(vec![relayer]),
+                Err(Error::Unauthorized)
+            );
         }
 
         #[ink::test]
@@ -267,6 +684,15 @@ mod std_ref {
 
             let mut std_ref = setup(admin, &relayers);
 
+            // Only the admin can remove relayers
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(relayers[0]);
+            assert_eq!(
+                std_ref.remove_relayers(relayers.clone()),
+                Err(Error::Unauthorized)
+            );
+
+            // Relayers are removed without waiting for the timelock
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(admin);
             assert_eq!(std_ref.remove_relayers(relayers.clone()), Ok(()));
             for relayer in relayers.clone() {
                 assert!(!std_ref.is_relayer(relayer));
@@ -276,7 +702,7 @@ mod std_ref {
         #[ink::test]
         fn test_relay_success() {
             let relay_admin = AccountId::from([0x01; 32]);
-            let mut std_ref = StandardReference::new(relay_admin);
+            let mut std_ref = setup(relay_admin, &[relay_admin]);
 
             let symbol_rates = vec![
                 ("BTC".to_string(), E9),
@@ -307,10 +733,10 @@ mod std_ref {
             let admin = AccountId::from([0x01; 32]);
             let relayer = AccountId::from([0x02; 32]);
 
-            let mut std_ref = StandardReference::new(admin);
-            let _ = std_ref.add_relayers(vec![relayer]);
+            let mut std_ref = setup(admin, &[relayer]);
 
             // Force relay successfully
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(relayer);
             let result = std_ref.force_relay(vec![("BTC".to_string(), E9)], 1, 1);
             assert_eq!(result, Ok(()));
 
@@ -324,7 +750,7 @@ mod std_ref {
         #[ink::test]
         fn test_successful_relay_overwrite() {
             let relay_admin = AccountId::from([0x01; 32]);
-            let mut std_ref = StandardReference::new(relay_admin);
+            let mut std_ref = setup(relay_admin, &[relay_admin]);
 
             let symbol_rates = vec![
                 ("BTC".to_string(), E9),
@@ -359,7 +785,7 @@ mod std_ref {
         #[ink::test]
         fn test_stale_relay() {
             let relay_admin = AccountId::from([0x01; 32]);
-            let mut std_ref = StandardReference::new(relay_admin);
+            let mut std_ref = setup(relay_admin, &[relay_admin]);
 
             let symbol_rates = vec![
                 ("BTC".to_string(), E9),
@@ -390,5 +816,525 @@ mod std_ref {
                 assert_eq!((o * E9) as u128, r.unwrap().rate);
             }
         }
+
+        #[ink::test]
+        fn test_upgrade_unauthorized() {
+            let admin = AccountId::from([0x01; 32]);
+            let mut std_ref = StandardReference::new(admin);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(AccountId::from([0x02; 32]));
+            assert_eq!(
+                std_ref.schedule_upgrade([0x01; 32]),
+                Err(Error::Unauthorized)
+            );
+            assert_eq!(std_ref.execute_upgrade(), Err(Error::Unauthorized));
+            assert_eq!(std_ref.cancel_upgrade(), Err(Error::Unauthorized));
+        }
+
+        #[ink::test]
+        fn test_timelock() {
+            let admin = AccountId::from([0x01; 32]);
+            let mut std_ref = StandardReference::new_with_timelock_delay(admin, 100);
+            assert_eq!(std_ref.timelock_delay(), 100);
+
+            set_block_timestamp(10);
+            assert_eq!(std_ref.schedule_upgrade([0x01; 32]), Ok(()));
+            assert_eq!(
+                std_ref.schedule_upgrade([0x02; 32]),
+                Err(Error::OperationAlreadyPending)
+            );
+            assert_eq!(
+                std_ref.pending_operation(OperationKind::Upgrade),
+                Some(PendingOperation::new(Operation::Upgrade([0x01; 32]), 110))
+            );
+
+            // Operations of different kinds can be pending at the same time
+            let new_admin = AccountId::from([0x02; 32]);
+            assert_eq!(std_ref.schedule_transfer_admin(new_admin), Ok(()));
+            assert_eq!(
+                std_ref.pending_operations(),
+                vec![
+                    PendingOperation::new(Operation::Upgrade([0x01; 32]), 110),
+                    PendingOperation::new(Operation::TransferAdmin(new_admin), 110),
+                ]
+            );
+
+            // Cancelled operations cannot be executed
+            assert_eq!(std_ref.cancel_upgrade(), Ok(()));
+            assert_eq!(std_ref.cancel_upgrade(), Err(Error::NoPendingOperation));
+            assert_eq!(std_ref.execute_upgrade(), Err(Error::NoPendingOperation));
+            assert_eq!(std_ref.pending_operation(OperationKind::Upgrade), None);
+
+            set_block_timestamp(109);
+            assert_eq!(
+                std_ref.execute_transfer_admin(),
+                Err(Error::TimelockNotExpired)
+            );
+            assert_eq!(std_ref.current_admin(), admin);
+
+            set_block_timestamp(110);
+            assert_eq!(std_ref.execute_transfer_admin(), Ok(()));
+            assert_eq!(std_ref.current_admin(), new_admin);
+            assert_eq!(std_ref.pending_operations(), vec![]);
+        }
+
+        #[ink::test]
+        fn test_timelock_delay() {
+            let admin = AccountId::from([0x01; 32]);
+            let mut std_ref = StandardReference::new_with_timelock_delay(admin, 100);
+
+            // The new delay only applies once the current delay has passed
+            assert_eq!(std_ref.schedule_timelock_delay(0), Ok(()));
+            assert_eq!(
+                std_ref.execute_timelock_delay(),
+                Err(Error::TimelockNotExpired)
+            );
+
+            set_block_timestamp(100);
+            assert_eq!(std_ref.execute_timelock_delay(), Ok(()));
+            assert_eq!(std_ref.timelock_delay(), 0);
+
+            assert_eq!(std_ref.schedule_timelock_delay(50), Ok(()));
+            assert_eq!(std_ref.cancel_timelock_delay(), Ok(()));
+            assert_eq!(std_ref.timelock_delay(), 0);
+        }
+
+        #[ink::test]
+        fn test_migrate() {
+            let admin = AccountId::from([0x01; 32]);
+            let mut std_ref = setup(admin, &[admin]);
+
+            // New deployments start at the current version
+            assert_eq!(std_ref.storage_version(), STORAGE_VERSION);
+            assert_eq!(std_ref.migrate(), Err(Error::AlreadyMigrated));
+
+            // Simulate a deployment predating storage versioning
+            std_ref.storage_version.set(&0);
+            assert_eq!(
+                std_ref.relay(vec![("BTC".to_string(), E9)], 1, 1),
+                Err(Error::MigrationRequired)
+            );
+            assert_eq!(
+                std_ref.force_relay(vec![("BTC".to_string(), E9)], 1, 1),
+                Err(Error::MigrationRequired)
+            );
+
+            // Only the admin can migrate
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(AccountId::from([0x02; 32]));
+            assert_eq!(std_ref.migrate(), Err(Error::Unauthorized));
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(admin);
+            assert_eq!(std_ref.migrate(), Ok(()));
+            assert_eq!(std_ref.storage_version(), STORAGE_VERSION);
+            assert_eq!(std_ref.relay(vec![("BTC".to_string(), E9)], 1, 1), Ok(()));
+
+            // Migration cannot be run twice
+            assert_eq!(std_ref.migrate(), Err(Error::AlreadyMigrated));
+        }
+
+        #[ink::test]
+        fn test_relayer_info() {
+            let admin = AccountId::from([0x01; 32]);
+            let relayer = AccountId::from([0x02; 32]);
+
+            set_block_timestamp(10);
+            let mut std_ref = setup(admin, &[relayer]);
+
+            assert_eq!(std_ref.get_relayer_info(AccountId::from([0x03; 32])), None);
+            assert_eq!(
+                std_ref.get_relayer_info(relayer),
+                Some(RelayerStatus {
+                    relayer,
+                    info: RelayerInfo::new(10),
+                    is_stale: false,
+                })
+            );
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(relayer);
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            set_block_timestamp(20);
+            let _ = std_ref.relay(vec![("BTC".to_string(), E9)], 1, 1);
+            let _ = std_ref.force_relay(vec![("BTC".to_string(), E9)], 1, 1);
+
+            let info = std_ref.get_relayer_info(relayer).unwrap().info;
+            assert_eq!(info.last_relay_block, 1);
+            assert_eq!(info.last_relay_time, 20);
+            assert_eq!(info.relay_count, 2);
+
+            // Failed relays are not recorded
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(AccountId::from([0x03; 32]));
+            let _ = std_ref.relay(vec![("BTC".to_string(), E9)], 2, 2);
+            assert_eq!(
+                std_ref.get_relayer_info(relayer).unwrap().info.relay_count,
+                2
+            );
+        }
+
+        #[ink::test]
+        fn test_backfill_relayers() {
+            let admin = AccountId::from([0x01; 32]);
+            let relayer = AccountId::from([0x02; 32]);
+            let mut std_ref = StandardReference::new_with_timelock_delay(admin, 0);
+
+            // Simulate a deployment predating storage versioning and liveness tracking
+            std_ref.storage_version.set(&0);
+            std_ref.relayers.insert(relayer, &());
+            assert_eq!(std_ref.migrate(), Ok(()));
+            assert_eq!(std_ref.list_relayers(), vec![]);
+
+            // Only the admin can backfill, and only existing relayers
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(relayer);
+            assert_eq!(
+                std_ref.backfill_relayers(vec![relayer]),
+                Err(Error::Unauthorized)
+            );
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(admin);
+            assert_eq!(
+                std_ref.backfill_relayers(vec![relayer, AccountId::from([0x03; 32])]),
+                Err(Error::InvalidValue)
+            );
+            assert_eq!(std_ref.list_relayers(), vec![]);
+
+            set_block_timestamp(10);
+            assert_eq!(std_ref.backfill_relayers(vec![relayer]), Ok(()));
+            assert_eq!(std_ref.backfill_relayers(vec![relayer]), Ok(()));
+            assert_eq!(
+                std_ref.list_relayers(),
+                vec![RelayerStatus {
+                    relayer,
+                    info: RelayerInfo::new(10),
+                    is_stale: false,
+                }]
+            );
+        }
+
+        #[ink::test]
+        fn test_stale_relayers() {
+            let admin = AccountId::from([0x01; 32]);
+            let relayer = AccountId::from([0x02; 32]);
+            let idle_relayer = AccountId::from([0x03; 32]);
+            let mut std_ref = setup(admin, &[relayer, idle_relayer]);
+
+            // Relayers are never stale without a threshold
+            set_block_timestamp(1_000);
+            assert!(std_ref.list_relayers().iter().all(|s| !s.is_stale));
+
+            assert_eq!(std_ref.set_inactivity_threshold(100), Ok(()));
+            assert_eq!(std_ref.inactivity_threshold(), 100);
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(relayer);
+            assert_eq!(
+                std_ref.set_inactivity_threshold(0),
+                Err(Error::Unauthorized)
+            );
+            let _ = std_ref.relay(vec![("BTC".to_string(), E9)], 1, 1);
+
+            set_block_timestamp(1_100);
+            let statuses = std_ref.list_relayers();
+            assert_eq!(
+                statuses
+                    .iter()
+                    .map(|s| (s.relayer, s.is_stale))
+                    .collect::<Vec<_>>(),
+                vec![(relayer, false), (idle_relayer, true)]
+            );
+
+            set_block_timestamp(1_101);
+            assert!(std_ref.get_relayer_info(relayer).unwrap().is_stale);
+
+            // Removed relayers are no longer listed
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(admin);
+            let _ = std_ref.remove_relayers(vec![relayer]);
+            assert_eq!(
+                std_ref
+                    .list_relayers()
+                    .iter()
+                    .map(|s| s.relayer)
+                    .collect::<Vec<_>>(),
+                vec![idle_relayer]
+            );
+            assert_eq!(std_ref.get_relayer_info(relayer), None);
+        }
+    }
+
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+
+        use crate::constant::E18;
+        use ink_e2e::build_message;
+        use updated_std_ref::updated_std_ref::StandardReferenceRef as UpdatedStandardReferenceRef;
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        #[ink_e2e::test]
+        async fn e2e_relay(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let admin = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+            let std_ref_id = client
+                .instantiate(
+                    "standard_reference",
+                    &ink_e2e::alice(),
+                    StandardReferenceRef::new_with_timelock_delay(admin, 0),
+                    0,
+                    None,
+                )
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            // The admin only relays once granted the role through the timelock
+            let schedule_add_relayers = build_message::<StandardReferenceRef>(std_ref_id)
+                .call(|std_ref| std_ref.schedule_add_relayers(vec![admin]));
+            client
+                .call(&ink_e2e::alice(), schedule_add_relayers, 0, None)
+                .await
+                .expect("schedule add relayers failed");
+            let execute_add_relayers = build_message::<StandardReferenceRef>(std_ref_id)
+                .call(|std_ref| std_ref.execute_add_relayers());
+            client
+                .call(&ink_e2e::alice(), execute_add_relayers, 0, None)
+                .await
+                .expect("execute add relayers failed");
+
+            let relay = build_message::<StandardReferenceRef>(std_ref_id).call(|std_ref| {
+                std_ref.relay(
+                    vec![("BTC".to_string(), 2 * E9), ("ETH".to_string(), E9)],
+                    10,
+                    1,
+                )
+            });
+            client
+                .call(&ink_e2e::alice(), relay, 0, None)
+                .await
+                .expect("relay failed");
+
+            let get_reference_data = build_message::<StandardReferenceRef>(std_ref_id)
+                .call(|std_ref| std_ref.get_reference_data(("BTC".to_string(), "ETH".to_string())));
+            let data = client
+                .call_dry_run(&ink_e2e::alice(), &get_reference_data, 0, None)
+                .await
+                .return_value()
+                .expect("reference data should exist");
+            assert_eq!(data.rate, 2 * E18);
+            assert_eq!(data.base_resolve_time, 10);
+            assert_eq!(data.quote_resolve_time, 10);
+
+            // Stale relay should not overwrite while force relay should
+            let relay = build_message::<StandardReferenceRef>(std_ref_id)
+                .call(|std_ref| std_ref.relay(vec![("BTC".to_string(), 3 * E9)], 5, 2));
+            client
+                .call(&ink_e2e::alice(), relay, 0, None)
+                .await
+                .expect("relay failed");
+
+            let get_reference_data = build_message::<StandardReferenceRef>(std_ref_id)
+                .call(|std_ref| std_ref.get_reference_data(("BTC".to_string(), USD.to_string())));
+            let data = client
+                .call_dry_run(&ink_e2e::alice(), &get_reference_data, 0, None)
+                .await
+                .return_value()
+                .expect("reference data should exist");
+            assert_eq!(data.rate, 2 * E18);
+
+            let force_relay = build_message::<StandardReferenceRef>(std_ref_id)
+                .call(|std_ref| std_ref.force_relay(vec![("BTC".to_string(), 3 * E9)], 5, 2));
+            client
+                .call(&ink_e2e::alice(), force_relay, 0, None)
+                .await
+                .expect("force relay failed");
+
+            let data = client
+                .call_dry_run(&ink_e2e::alice(), &get_reference_data, 0, None)
+                .await
+                .return_value()
+                .expect("reference data should exist");
+            assert_eq!(data.rate, 3 * E18);
+            assert_eq!(data.base_resolve_time, 5);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn e2e_relay_unauthorized(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let admin = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+            let std_ref_id = client
+                .instantiate(
+                    "standard_reference",
+                    &ink_e2e::alice(),
+                    StandardReferenceRef::new(admin),
+                    0,
+                    None,
+                )
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            let relay = build_message::<StandardReferenceRef>(std_ref_id)
+                .call(|std_ref| std_ref.relay(vec![("BTC".to_string(), E9)], 1, 1));
+            let result = client
+                .call_dry_run(&ink_e2e::bob(), &relay, 0, None)
+                .await
+                .return_value();
+            assert_eq!(result, Err(Error::Unauthorized));
+
+            let get_reference_data = build_message::<StandardReferenceRef>(std_ref_id)
+                .call(|std_ref| std_ref.get_reference_data(("BTC".to_string(), USD.to_string())));
+            let result = client
+                .call_dry_run(&ink_e2e::bob(), &get_reference_data, 0, None)
+                .await
+                .return_value();
+            assert_eq!(result.err(), Some(Error::PairDoesNotExist));
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(additional_contracts = "../updated_std_ref/Cargo.toml")]
+        async fn e2e_upgrade(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let admin = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+            let std_ref_id = client
+                .instantiate(
+                    "standard_reference",
+                    &ink_e2e::alice(),
+                    StandardReferenceRef::new_with_timelock_delay(admin, 0),
+                    0,
+                    None,
+                )
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            // The admin only relays once granted the role through the timelock
+            let schedule_add_relayers = build_message::<StandardReferenceRef>(std_ref_id)
+                .call(|std_ref| std_ref.schedule_add_relayers(vec![admin]));
+            client
+                .call(&ink_e2e::alice(), schedule_add_relayers, 0, None)
+                .await
+                .expect("schedule add relayers failed");
+            let execute_add_relayers = build_message::<StandardReferenceRef>(std_ref_id)
+                .call(|std_ref| std_ref.execute_add_relayers());
+            client
+                .call(&ink_e2e::alice(), execute_add_relayers, 0, None)
+                .await
+                .expect("execute add relayers failed");
+
+            let relay = build_message::<StandardReferenceRef>(std_ref_id)
+                .call(|std_ref| std_ref.relay(vec![("BTC".to_string(), 2 * E9)], 1, 1));
+            client
+                .call(&ink_e2e::alice(), relay, 0, None)
+                .await
+                .expect("relay failed");
+
+            let code_hash = client
+                .upload("updated_std_ref", &ink_e2e::alice(), None)
+                .await
+                .expect("upload failed")
+                .code_hash;
+            let code_hash: [u8; 32] = code_hash.as_ref().try_into().unwrap();
+
+            // Only the admin can schedule an upgrade
+            let schedule_upgrade = build_message::<StandardReferenceRef>(std_ref_id)
+                .call(|std_ref| std_ref.schedule_upgrade(code_hash));
+            let result = client
+                .call_dry_run(&ink_e2e::bob(), &schedule_upgrade, 0, None)
+                .await
+                .return_value();
+            assert_eq!(result, Err(Error::Unauthorized));
+
+            // Unknown code hashes are rejected without panicking
+            let schedule_bad_upgrade = build_message::<StandardReferenceRef>(std_ref_id)
+                .call(|std_ref| std_ref.schedule_upgrade([0xff; 32]));
+            client
+                .call(&ink_e2e::alice(), schedule_bad_upgrade, 0, None)
+                .await
+                .expect("schedule upgrade failed");
+
+            let execute_upgrade = build_message::<StandardReferenceRef>(std_ref_id)
+                .call(|std_ref| std_ref.execute_upgrade());
+            let result = client
+                .call_dry_run(&ink_e2e::alice(), &execute_upgrade, 0, None)
+                .await
+                .return_value();
+            assert_eq!(result, Err(Error::UpgradeFailed));
+
+            let cancel_upgrade = build_message::<StandardReferenceRef>(std_ref_id)
+                .call(|std_ref| std_ref.cancel_upgrade());
+            client
+                .call(&ink_e2e::alice(), cancel_upgrade, 0, None)
+                .await
+                .expect("cancel upgrade failed");
+
+            client
+                .call(&ink_e2e::alice(), schedule_upgrade, 0, None)
+                .await
+                .expect("schedule upgrade failed");
+            let result = client
+                .call(&ink_e2e::alice(), execute_upgrade, 0, None)
+                .await
+                .expect("execute upgrade failed")
+                .return_value();
+            assert_eq!(result, Ok(()));
+
+            // The new code is running on top of the existing storage
+            let version = build_message::<UpdatedStandardReferenceRef>(std_ref_id)
+                .call(|std_ref| std_ref.version());
+            let version = client
+                .call_dry_run(&ink_e2e::alice(), &version, 0, None)
+                .await
+                .return_value();
+            assert_eq!(version, 2);
+
+            let get_rate = build_message::<UpdatedStandardReferenceRef>(std_ref_id)
+                .call(|std_ref| std_ref.get_rate("BTC".to_string()));
+            let rate = client
+                .call_dry_run(&ink_e2e::alice(), &get_rate, 0, None)
+                .await
+                .return_value();
+            assert_eq!(rate, Some(2 * E9));
+
+            let current_admin = build_message::<UpdatedStandardReferenceRef>(std_ref_id)
+                .call(|std_ref| std_ref.current_admin());
+            let current_admin = client
+                .call_dry_run(&ink_e2e::alice(), &current_admin, 0, None)
+                .await
+                .return_value();
+            assert_eq!(current_admin, admin);
+
+            // The new code migrates the storage exactly once
+            let storage_version = build_message::<UpdatedStandardReferenceRef>(std_ref_id)
+                .call(|std_ref| std_ref.storage_version());
+            let version = client
+                .call_dry_run(&ink_e2e::alice(), &storage_version, 0, None)
+                .await
+                .return_value();
+            assert_eq!(version, STORAGE_VERSION);
+
+            let migrate = build_message::<UpdatedStandardReferenceRef>(std_ref_id)
+                .call(|std_ref| std_ref.migrate());
+            let result = client
+                .call(&ink_e2e::alice(), migrate, 0, None)
+                .await
+                .expect("migrate failed")
+                .return_value();
+            assert_eq!(result, Ok(()));
+
+            let version = client
+                .call_dry_run(&ink_e2e::alice(), &storage_version, 0, None)
+                .await
+                .return_value();
+            assert_eq!(version, 2);
+
+            let migrate = build_message::<UpdatedStandardReferenceRef>(std_ref_id)
+                .call(|std_ref| std_ref.migrate());
+            let result = client
+                .call_dry_run(&ink_e2e::alice(), &migrate, 0, None)
+                .await
+                .return_value();
+            assert_eq!(
+                result,
+                Err(updated_std_ref::updated_std_ref::Error::AlreadyMigrated)
+            );
+
+            Ok(())
+        }
     }
 }
@@ -5,7 +5,6 @@ use scale::{Decode, Encode};
     feature = "std",
     derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
 )]
-
 pub struct RefDatum {
     pub rate: u64,
     pub resolve_time: u64,
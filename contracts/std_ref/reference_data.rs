@@ -4,7 +4,6 @@ use crate::std_ref::Error;
 
 #[derive(scale::Decode, scale::Encode)]
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
-
 pub struct ReferenceData {
     pub rate: u128,
     pub base_resolve_time: u64,
@@ -0,0 +1,45 @@
+use ink::primitives::AccountId;
+use scale::{Decode, Encode};
+
+/// Liveness information recorded for a relayer
+#[derive(Encode, Decode, Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct RelayerInfo {
+    pub added_at: u64,
+    pub last_relay_block: u32,
+    pub last_relay_time: u64,
+    pub relay_count: u64,
+}
+
+impl RelayerInfo {
+    pub fn new(added_at: u64) -> Self {
+        Self {
+            added_at,
+            ..Default::default()
+        }
+    }
+
+    pub fn record_relay(&mut self, block: u32, time: u64) {
+        self.last_relay_block = block;
+        self.last_relay_time = time;
+        self.relay_count = self.relay_count.saturating_add(1);
+    }
+
+    /// Returns the time of the last activity, which is when the relayer was
+    /// added if it has never relayed.
+    pub fn last_active_time(&self) -> u64 {
+        self.last_relay_time.max(self.added_at)
+    }
+}
+
+/// Relayer liveness as reported by the contract
+#[derive(Encode, Decode, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct RelayerStatus {
+    pub relayer: AccountId,
+    pub info: RelayerInfo,
+    pub is_stale: bool,
+}
@@ -0,0 +1,66 @@
+use ink::prelude::vec::Vec;
+use ink::primitives::AccountId;
+use scale::{Decode, Encode};
+
+/// Kinds of admin operations that must go through the timelock
+#[derive(Encode, Decode, Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum OperationKind {
+    Upgrade,
+    TransferAdmin,
+    AddRelayers,
+    SetTimelockDelay,
+}
+
+impl OperationKind {
+    pub const ALL: [OperationKind; 4] = [
+        OperationKind::Upgrade,
+        OperationKind::TransferAdmin,
+        OperationKind::AddRelayers,
+        OperationKind::SetTimelockDelay,
+    ];
+}
+
+/// Admin operation along with its arguments
+#[derive(Encode, Decode, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub enum Operation {
+    Upgrade([u8; 32]),
+    TransferAdmin(AccountId),
+    AddRelayers(Vec<AccountId>),
+    SetTimelockDelay(u64),
+}
+
+impl Operation {
+    pub fn kind(&self) -> OperationKind {
+        match self {
+            Operation::Upgrade(_) => OperationKind::Upgrade,
+            Operation::TransferAdmin(_) => OperationKind::TransferAdmin,
+            Operation::AddRelayers(_) => OperationKind::AddRelayers,
+            Operation::SetTimelockDelay(_) => OperationKind::SetTimelockDelay,
+        }
+    }
+}
+
+/// Admin operation waiting for the timelock delay to pass
+#[derive(Encode, Decode, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+)]
+pub struct PendingOperation {
+    pub operation: Operation,
+    pub executable_at: u64,
+}
+
+impl PendingOperation {
+    pub fn new(operation: Operation, executable_at: u64) -> Self {
+        Self {
+            operation,
+            executable_at,
+        }
+    }
+}
@@ -0,0 +1,202 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+/// Example contract consuming prices from the StandardReference contract
+/// through `std_ref_interface`.
+#[ink::contract]
+pub mod std_ref_consumer {
+    use ink::prelude::string::String;
+    use ink::prelude::vec::Vec;
+
+    use std_ref_interface::{Result, StandardReferenceQuery, StandardReferenceRef};
+
+    #[ink(storage)]
+    pub struct StdRefConsumer {
+        /// Reference to the StandardReference contract
+        std_ref: StandardReferenceRef,
+    }
+
+    impl StdRefConsumer {
+        /// Creates a new StdRefConsumer Contract
+        #[ink(constructor)]
+        pub fn new(std_ref: AccountId) -> Self {
+            Self {
+                std_ref: std_ref.into(),
+            }
+        }
+
+        /// Returns the rate of the given base/quote pair multiplied by 1e18.
+        #[ink(message)]
+        pub fn get_price(&self, base: String, quote: String) -> Result<u128> {
+            self.std_ref
+                .get_reference_data((base, quote))
+                .map(|data| data.rate)
+        }
+
+        /// Returns the rates of multiple base/quote pairs at once.
+        #[ink(message)]
+        pub fn get_prices(&self, symbol_pairs: Vec<(String, String)>) -> Vec<Result<u128>> {
+            self.std_ref
+                .get_reference_data_bulk(symbol_pairs)
+                .into_iter()
+                .map(|data| data.map(|d| d.rate))
+                .collect()
+        }
+    }
+
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+
+        use ink_e2e::build_message;
+        use standard_reference::std_ref::StandardReferenceRef as StdRefDeployRef;
+        use std_ref_interface::Error;
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        const E9: u64 = 1_000_000_000u64;
+        const E18: u128 = 1_000_000_000_000_000_000u128;
+
+        #[ink_e2e::test(additional_contracts = "../std_ref/Cargo.toml")]
+        async fn e2e_get_price(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let admin = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+            let std_ref_id = client
+                .instantiate(
+                    "standard_reference",
+                    &ink_e2e::alice(),
+                    StdRefDeployRef::new_with_timelock_delay(admin, 0),
+                    0,
+                    None,
+                )
+                .await
+                .expect("std_ref instantiate failed")
+                .account_id;
+
+            // The admin only relays once granted the role through the timelock
+            let schedule_add_relayers = build_message::<StdRefDeployRef>(std_ref_id)
+                .call(|std_ref| std_ref.schedule_add_relayers(vec![admin]));
+            client
+                .call(&ink_e2e::alice(), schedule_add_relayers, 0, None)
+                .await
+                .expect("schedule add relayers failed");
+            let execute_add_relayers = build_message::<StdRefDeployRef>(std_ref_id)
+                .call(|std_ref| std_ref.execute_add_relayers());
+            client
+                .call(&ink_e2e::alice(), execute_add_relayers, 0, None)
+                .await
+                .expect("execute add relayers failed");
+
+            let relay = build_message::<StdRefDeployRef>(std_ref_id).call(|std_ref| {
+                std_ref.relay(
+                    vec![
+                        ("BTC".to_string(), 30_000 * E9),
+                        ("ETH".to_string(), 2_000 * E9),
+                    ],
+                    1,
+                    1,
+                )
+            });
+            client
+                .call(&ink_e2e::alice(), relay, 0, None)
+                .await
+                .expect("relay failed");
+
+            let consumer_id = client
+                .instantiate(
+                    "std_ref_consumer",
+                    &ink_e2e::alice(),
+                    StdRefConsumerRef::new(std_ref_id),
+                    0,
+                    None,
+                )
+                .await
+                .expect("consumer instantiate failed")
+                .account_id;
+
+            let get_price = build_message::<StdRefConsumerRef>(consumer_id)
+                .call(|consumer| consumer.get_price("BTC".to_string(), "ETH".to_string()));
+            let price = client
+                .call_dry_run(&ink_e2e::alice(), &get_price, 0, None)
+                .await
+                .return_value();
+            assert_eq!(price, Ok(15 * E18));
+
+            let get_prices = build_message::<StdRefConsumerRef>(consumer_id).call(|consumer| {
+                consumer.get_prices(vec![
+                    ("ETH".to_string(), "USD".to_string()),
+                    ("DNE".to_string(), "USD".to_string()),
+                ])
+            });
+            let prices = client
+                .call_dry_run(&ink_e2e::alice(), &get_prices, 0, None)
+                .await
+                .return_value();
+            assert_eq!(prices, vec![Ok(2_000 * E18), Err(Error::PairDoesNotExist)]);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(additional_contracts = "../std_ref/Cargo.toml")]
+        async fn e2e_get_price_after_relay(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            let admin = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+            let std_ref_id = client
+                .instantiate(
+                    "standard_reference",
+                    &ink_e2e::alice(),
+                    StdRefDeployRef::new_with_timelock_delay(admin, 0),
+                    0,
+                    None,
+                )
+                .await
+                .expect("std_ref instantiate failed")
+                .account_id;
+
+            // The admin only relays once granted the role through the timelock
+            let schedule_add_relayers = build_message::<StdRefDeployRef>(std_ref_id)
+                .call(|std_ref| std_ref.schedule_add_relayers(vec![admin]));
+            client
+                .call(&ink_e2e::alice(), schedule_add_relayers, 0, None)
+                .await
+                .expect("schedule add relayers failed");
+            let execute_add_relayers = build_message::<StdRefDeployRef>(std_ref_id)
+                .call(|std_ref| std_ref.execute_add_relayers());
+            client
+                .call(&ink_e2e::alice(), execute_add_relayers, 0, None)
+                .await
+                .expect("execute add relayers failed");
+
+            let consumer_id = client
+                .instantiate(
+                    "std_ref_consumer",
+                    &ink_e2e::alice(),
+                    StdRefConsumerRef::new(std_ref_id),
+                    0,
+                    None,
+                )
+                .await
+                .expect("consumer instantiate failed")
+                .account_id;
+
+            let get_price = build_message::<StdRefConsumerRef>(consumer_id)
+                .call(|consumer| consumer.get_price("BTC".to_string(), "USD".to_string()));
+
+            // Prices relayed after the consumer is deployed are visible to it
+            for (rate, resolve_time) in [(30_000 * E9, 1), (31_000 * E9, 2)] {
+                let relay = build_message::<StdRefDeployRef>(std_ref_id).call(|std_ref| {
+                    std_ref.relay(vec![("BTC".to_string(), rate)], resolve_time, resolve_time)
+                });
+                client
+                    .call(&ink_e2e::alice(), relay, 0, None)
+                    .await
+                    .expect("relay failed");
+
+                let price = client
+                    .call_dry_run(&ink_e2e::alice(), &get_price, 0, None)
+                    .await
+                    .return_value();
+                assert_eq!(price, Ok(rate as u128 * E18 / E9 as u128));
+            }
+
+            Ok(())
+        }
+    }
+}
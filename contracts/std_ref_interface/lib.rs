@@ -0,0 +1,71 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use ink::env::DefaultEnvironment;
+use ink::prelude::string::String;
+use ink::prelude::vec::Vec;
+
+/// Errors returned by the StandardReference contract.
+///
+/// # Note
+///
+/// The variant order must match `standard_reference::std_ref::Error` since
+/// results are decoded by index.
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum Error {
+    /// Returned if the pair is invalid.
+    PairDoesNotExist,
+    /// Returned if the value is invalid.
+    InvalidValue,
+    /// Returned if unauthorized caller tries to call a function that requires authorization.
+    Unauthorized,
+    /// Returned if the code hash could not be set.
+    UpgradeFailed,
+    /// Returned if the storage is already at the current version.
+    AlreadyMigrated,
+    /// Returned if the storage must be migrated before the call can be made.
+    MigrationRequired,
+    /// Returned if an operation of the same kind is already scheduled.
+    OperationAlreadyPending,
+    /// Returned if no operation of the given kind is scheduled.
+    NoPendingOperation,
+    /// Returned if the timelock delay of the operation has not passed yet.
+    TimelockNotExpired,
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// Reference data of a base/quote pair as returned by the StandardReference contract.
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct ReferenceData {
+    /// Pair rate multiplied by 1e18
+    pub rate: u128,
+    /// Unix time of when the base asset was last updated
+    pub base_resolve_time: u64,
+    /// Unix time of when the quote asset was last updated
+    pub quote_resolve_time: u64,
+}
+
+/// Query surface of the StandardReference contract.
+///
+/// The selectors are pinned to the ones of the inherent messages of the
+/// deployed contract so that it can be called through this trait.
+#[ink::trait_definition]
+pub trait StandardReferenceQuery {
+    /// Returns the reference data for a given symbol pair.
+    #[ink(message, selector = 0x6ce7fb13)]
+    fn get_reference_data(&self, symbol_pair: (String, String)) -> Result<ReferenceData>;
+
+    /// Returns the reference data for multiple symbol pairs at once.
+    #[ink(message, selector = 0xc3fcd7bd)]
+    fn get_reference_data_bulk(
+        &self,
+        symbol_pairs: Vec<(String, String)>,
+    ) -> Vec<Result<ReferenceData>>;
+}
+
+/// Typed reference to a deployed StandardReference contract.
+///
+/// Can be created from the contract's `AccountId` with `into()`.
+pub type StandardReferenceRef = ink::contract_ref!(StandardReferenceQuery, DefaultEnvironment);
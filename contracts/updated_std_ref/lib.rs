@@ -0,0 +1,102 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+/// Upgrade target used by the StandardReference e2e tests.
+///
+/// # Note
+///
+/// The storage struct name and fields must stay identical to
+/// `standard_reference::std_ref::StandardReference` since ink! derives the
+/// storage keys from them.
+#[ink::contract]
+pub mod updated_std_ref {
+    use ink::prelude::string::String;
+    use ink::storage::{Lazy, Mapping};
+
+    const STORAGE_VERSION: u32 = 2;
+
+    #[derive(scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct RefDatum {
+        pub rate: u64,
+        pub resolve_time: u64,
+        pub request_id: u64,
+    }
+
+    #[ink(storage)]
+    pub struct StandardReference {
+        /// Address of admin who can grant/revoke relayers
+        admin: AccountId,
+        /// Mapping of the granted relayers
+        relayers: Mapping<AccountId, ()>,
+        /// Mapping from symbol to reference datum
+        ref_data: Mapping<String, RefDatum>,
+        /// Version of the storage layout, unset for deployments predating versioning
+        storage_version: Lazy<u32>,
+    }
+
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Error {
+        Unauthorized,
+        AlreadyMigrated,
+    }
+
+    impl StandardReference {
+        /// Creates a new StandardReference Contract
+        #[ink(constructor)]
+        pub fn new(admin: AccountId) -> Self {
+            Self {
+                admin,
+                relayers: Mapping::new(),
+                ref_data: Mapping::new(),
+                storage_version: Lazy::new(),
+            }
+        }
+
+        /// Migrates the storage to the layout of this code.
+        #[ink(message)]
+        pub fn migrate(&mut self) -> Result<(), Error> {
+            if self.admin != self.env().caller() {
+                return Err(Error::Unauthorized);
+            }
+            if self.storage_version() >= STORAGE_VERSION {
+                return Err(Error::AlreadyMigrated);
+            }
+            self.storage_version.set(&STORAGE_VERSION);
+            Ok(())
+        }
+
+        /// Returns the version of the storage layout.
+        #[ink(message)]
+        pub fn storage_version(&self) -> u32 {
+            self.storage_version.get().unwrap_or(0)
+        }
+
+        /// Returns the version of the contract code.
+        #[ink(message)]
+        pub fn version(&self) -> u32 {
+            2
+        }
+
+        /// Returns the account ID of the current contract admin.
+        #[ink(message)]
+        pub fn current_admin(&self) -> AccountId {
+            self.admin
+        }
+
+        /// Checks if caller is relayer.
+        #[ink(message)]
+        pub fn is_relayer(&self, relayer: AccountId) -> bool {
+            self.relayers.contains(relayer)
+        }
+
+        /// Returns the stored rate of a symbol.
+        #[ink(message)]
+        pub fn get_rate(&self, symbol: String) -> Option<u64> {
+            self.ref_data.get(symbol).map(|ref_datum| ref_datum.rate)
+        }
+    }
+}
@@ -0,0 +1,462 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+use std::{env, fs};
+
+const SYMBOLS_FILE: &str = "symbols.toml";
+const USD: &str = "USD";
+const DECIMALS: usize = 18;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    classes: Classes,
+    requests: Requests,
+    sources: BTreeMap<String, Source>,
+    symbols: BTreeMap<String, Symbol>,
+}
+
+/// Validation parameters of the symbol classes
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Classes {
+    stablecoin: ClassParams,
+    wrapped: ClassParams,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ClassParams {
+    max_deviation_bps: u64,
+}
+
+/// Limits on the raw requests issued by an oracle request
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Requests {
+    max_raw_request_count: usize,
+    max_chunk_symbols: usize,
+    max_chunk_calldata_size: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Source {
+    mainnet: i64,
+    testnet: i64,
+    weight: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(from = "SymbolEntry")]
+struct Symbol {
+    sources: Vec<String>,
+    fallback_sources: Vec<String>,
+    minimum_source_count: Option<u8>,
+    quote: String,
+    class: Class,
+    underlying: Option<String>,
+    derived_from: Option<String>,
+    ratio: String,
+}
+
+#[derive(Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Class {
+    #[default]
+    Volatile,
+    Stablecoin,
+    Wrapped,
+}
+
+impl Symbol {
+    /// Returns the data sources of both tiers
+    fn all_sources(&self) -> impl Iterator<Item = &String> {
+        self.sources.iter().chain(&self.fallback_sources)
+    }
+}
+
+/// A symbol is either given as the list of its data sources or as a table
+#[derive(Deserialize)]
+#[serde(untagged, deny_unknown_fields)]
+enum SymbolEntry {
+    Sources(Vec<String>),
+    Table {
+        #[serde(default)]
+        sources: Vec<String>,
+        #[serde(default)]
+        fallback_sources: Vec<String>,
+        #[serde(default)]
+        minimum_source_count: Option<u8>,
+        #[serde(default = "default_quote")]
+        quote: String,
+        #[serde(default)]
+        class: Class,
+        #[serde(default)]
+        underlying: Option<String>,
+        #[serde(default)]
+        derived_from: Option<String>,
+        #[serde(default = "default_ratio")]
+        ratio: String,
+    },
+}
+
+fn default_quote() -> String {
+    USD.to_string()
+}
+
+fn default_ratio() -> String {
+    "1".to_string()
+}
+
+impl From<SymbolEntry> for Symbol {
+    fn from(entry: SymbolEntry) -> Self {
+        match entry {
+            SymbolEntry::Sources(sources) => Symbol {
+                sources,
+                fallback_sources: Vec::new(),
+                minimum_source_count: None,
+                quote: default_quote(),
+                class: Class::Volatile,
+                underlying: None,
+                derived_from: None,
+                ratio: default_ratio(),
+            },
+            SymbolEntry::Table {
+                sources,
+                fallback_sources,
+                minimum_source_count,
+                quote,
+                class,
+                underlying,
+                derived_from,
+                ratio,
+            } => Symbol {
+                sources,
+                fallback_sources,
+                minimum_source_count,
+                quote,
+                class,
+                underlying,
+                derived_from,
+                ratio,
+            },
+        }
+    }
+}
+
+/// Formats the given data sources as a slice expression
+fn sources_slice(sources: &[String]) -> String {
+    let sources = sources
+        .iter()
+        .map(|s| format!("DataSources::{s}"))
+        .collect::<Vec<String>>()
+        .join(", ");
+    format!("&[{sources}]")
+}
+
+/// Parses a decimal ratio into its value scaled by 10^18
+fn parse_ratio(ratio: &str) -> Option<u128> {
+    let (integer, fraction) = ratio.split_once('.').unwrap_or((ratio, ""));
+    if integer.is_empty()
+        || fraction.len() > DECIMALS
+        || !integer
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    integer
+        .parse::<u128>()
+        .ok()?
+        .checked_mul(10u128.pow(DECIMALS as u32))?
+        .checked_add(format!("{fraction:0<DECIMALS$}").parse().ok()?)
+}
+
+/// Formats the underlying symbol and ratio of a derived symbol as a `Derived` expression
+fn derived_expr(symbol: &Symbol) -> String {
+    match &symbol.derived_from {
+        Some(underlying) => format!(
+            "Some(Derived {{ underlying: {underlying:?}, ratio: Decimal::from_raw({}) }})",
+            parse_ratio(&symbol.ratio).unwrap()
+        ),
+        None => "None".to_string(),
+    }
+}
+
+/// Returns the symbol the data sources of the given symbol quote its rate in
+///
+/// Derived symbols take the quote of their underlying symbol.
+fn quote_of<'a>(config: &'a Config, symbol: &'a Symbol) -> &'a str {
+    match symbol
+        .derived_from
+        .as_ref()
+        .and_then(|u| config.symbols.get(u))
+    {
+        Some(underlying) => &underlying.quote,
+        None => &symbol.quote,
+    }
+}
+
+/// Formats the class of the given symbol as a `SymbolClass` expression
+fn class_expr(symbol: &Symbol) -> String {
+    match (&symbol.class, &symbol.underlying) {
+        (Class::Stablecoin, _) => "SymbolClass::Stablecoin".to_string(),
+        (Class::Wrapped, Some(underlying)) => format!("SymbolClass::Wrapped({underlying:?})"),
+        _ => "SymbolClass::Volatile".to_string(),
+    }
+}
+
+/// Generates the `DataSources` enum and the `SYMBOLS` map from the symbols file
+fn generate(config: &Config) -> String {
+    let mut code = String::new();
+
+    writeln!(
+        code,
+        "const DATA_SOURCE_COUNT: usize = {};",
+        config.sources.len()
+    )
+    .unwrap();
+    writeln!(
+        code,
+        "const STABLECOIN_MAX_DEVIATION_BPS: u64 = {};",
+        config.classes.stablecoin.max_deviation_bps
+    )
+    .unwrap();
+    writeln!(
+        code,
+        "const WRAPPED_MAX_DEVIATION_BPS: u64 = {};",
+        config.classes.wrapped.max_deviation_bps
+    )
+    .unwrap();
+    writeln!(
+        code,
+        "const MAX_RAW_REQUEST_COUNT: usize = {};",
+        config.requests.max_raw_request_count
+    )
+    .unwrap();
+    writeln!(
+        code,
+        "const MAX_CHUNK_SYMBOLS: usize = {};",
+        config.requests.max_chunk_symbols
+    )
+    .unwrap();
+    writeln!(
+        code,
+        "const MAX_CHUNK_CALLDATA_SIZE: usize = {};\n",
+        config.requests.max_chunk_calldata_size
+    )
+    .unwrap();
+
+    code.push_str("/// Data sources the oracle script can request rates from\n");
+    code.push_str("#[allow(clippy::upper_case_acronyms)]\n");
+    code.push_str("#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]\n");
+    code.push_str("pub enum DataSources {\n");
+    for name in config.sources.keys() {
+        writeln!(code, "    {name},").unwrap();
+    }
+    code.push_str("}\n\n");
+
+    code.push_str("impl DataSources {\n");
+    code.push_str("    /// Every registered data source, ordered by name\n");
+    code.push_str("    pub const ALL: [DataSources; DATA_SOURCE_COUNT] = [\n");
+    for name in config.sources.keys() {
+        writeln!(code, "        DataSources::{name},").unwrap();
+    }
+    code.push_str("    ];\n\n");
+    code.push_str("    /// Returns the name of the data source\n");
+    code.push_str("    pub fn name(&self) -> &'static str {\n");
+    code.push_str("        match self {\n");
+    for name in config.sources.keys() {
+        writeln!(code, "            DataSources::{name} => {name:?},").unwrap();
+    }
+    code.push_str("        }\n    }\n\n");
+    code.push_str("    /// Returns the id of the data source on the given network\n");
+    code.push_str("    pub fn id(&self, network: Network) -> i64 {\n");
+    code.push_str("        match (self, network) {\n");
+    for (name, source) in &config.sources {
+        writeln!(
+            code,
+            "            (DataSources::{name}, Network::Mainnet) => {},",
+            source.mainnet
+        )
+        .unwrap();
+        writeln!(
+            code,
+            "            (DataSources::{name}, Network::Testnet) => {},",
+            source.testnet
+        )
+        .unwrap();
+    }
+    code.push_str("        }\n    }\n\n");
+    code.push_str(
+        "    /// Returns the weight given to the data source when aggregating its rates\n",
+    );
+    code.push_str("    pub fn weight(&self) -> u64 {\n");
+    code.push_str("        match self {\n");
+    for (name, source) in &config.sources {
+        writeln!(
+            code,
+            "            DataSources::{name} => {},",
+            source.weight
+        )
+        .unwrap();
+    }
+    code.push_str("        }\n    }\n}\n\n");
+
+    code.push_str("static SYMBOLS: phf::Map<&'static str, SymbolInfo> = phf::phf_map! {\n");
+    for (name, symbol) in &config.symbols {
+        writeln!(
+            code,
+            "    {name:?} => SymbolInfo {{ sources: {}, fallback_sources: {}, minimum_source_count: {:?}, quote: {:?}, class: {}, derived: {} }},",
+            sources_slice(&symbol.sources),
+            sources_slice(&symbol.fallback_sources),
+            symbol.minimum_source_count,
+            quote_of(config, symbol),
+            class_expr(symbol),
+            derived_expr(symbol)
+        )
+        .unwrap();
+    }
+    code.push_str("};\n");
+
+    code
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed={SYMBOLS_FILE}");
+
+    let content = fs::read_to_string(SYMBOLS_FILE).expect("failed to read symbols file");
+    let config: Config = toml::from_str(&content).expect("failed to parse symbols file");
+
+    for network in ["mainnet", "testnet"] {
+        let mut ids = config
+            .sources
+            .values()
+            .map(|s| {
+                if network == "mainnet" {
+                    s.mainnet
+                } else {
+                    s.testnet
+                }
+            })
+            .collect::<Vec<i64>>();
+        ids.sort();
+        ids.dedup();
+        assert!(
+            ids.len() == config.sources.len(),
+            "data source ids are not unique on {network}"
+        );
+    }
+
+    assert!(
+        config.requests.max_raw_request_count > 0
+            && config.requests.max_chunk_symbols > 0
+            && config.requests.max_chunk_calldata_size > 0,
+        "raw request limits must be positive"
+    );
+
+    for (name, symbol) in &config.symbols {
+        // Derived symbols are computed from their underlying symbol and have no data sources
+        if let Some(underlying) = &symbol.derived_from {
+            assert!(
+                config
+                    .symbols
+                    .get(underlying)
+                    .is_some_and(|u| u.derived_from.is_none()),
+                "symbol {name} is derived from an unsupported symbol {underlying}"
+            );
+            assert!(
+                parse_ratio(&symbol.ratio).is_some_and(|r| r > 0),
+                "symbol {name} has an invalid ratio"
+            );
+            assert!(
+                symbol.all_sources().count() == 0
+                    && symbol.minimum_source_count.is_none()
+                    && symbol.class == Class::Volatile
+                    && symbol.underlying.is_none(),
+                "derived symbol {name} has data sources or a class"
+            );
+            continue;
+        }
+        assert!(
+            symbol.ratio == default_ratio(),
+            "symbol {name} has a ratio but is not derived"
+        );
+        assert!(
+            !symbol.sources.is_empty(),
+            "symbol {name} has no data sources"
+        );
+        for source in symbol.all_sources() {
+            assert!(
+                config.sources.contains_key(source),
+                "symbol {name} uses unknown data source {source}"
+            );
+        }
+        assert!(
+            !symbol
+                .fallback_sources
+                .iter()
+                .any(|s| symbol.sources.contains(s)),
+            "symbol {name} lists a data source in both tiers"
+        );
+        if let Some(count) = symbol.minimum_source_count {
+            assert!(
+                count > 0 && count as usize <= symbol.all_sources().count(),
+                "symbol {name} has an invalid minimum source count"
+            );
+        }
+
+        // Wrapped symbols are cross-checked against an unwrapped symbol with the same quote
+        match (&symbol.class, &symbol.underlying) {
+            (Class::Wrapped, Some(underlying)) => {
+                let underlying_symbol = config.symbols.get(underlying);
+                assert!(
+                    underlying_symbol
+                        .is_some_and(|u| u.class != Class::Wrapped && u.quote == symbol.quote),
+                    "symbol {name} wraps an unsupported symbol {underlying}"
+                );
+            }
+            (Class::Wrapped, None) => panic!("wrapped symbol {name} has no underlying symbol"),
+            (_, Some(_)) => panic!("symbol {name} has an underlying symbol but is not wrapped"),
+            _ => {}
+        }
+
+        // Following the quotes of the data sources must lead to USD
+        let mut quote = symbol.quote.as_str();
+        for _ in 0..config.symbols.len() {
+            match config.symbols.get(quote) {
+                Some(quote_symbol) => quote = quote_of(&config, quote_symbol),
+                None => break,
+            }
+        }
+        assert!(
+            quote == USD,
+            "symbol {name} is not quoted in USD or a supported symbol"
+        );
+    }
+
+    // Every data source is asked for all of its symbols in a single raw request
+    assert!(
+        config.sources.len() <= config.requests.max_raw_request_count,
+        "data sources exceed the raw request count limit"
+    );
+    for source in config.sources.keys() {
+        let symbols = config
+            .symbols
+            .iter()
+            .filter(|(_, symbol)| symbol.all_sources().any(|s| s == source))
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<&str>>();
+        assert!(
+            symbols.len() <= config.requests.max_chunk_symbols
+                && symbols.join(" ").len() <= config.requests.max_chunk_calldata_size,
+            "symbols of data source {source} exceed the chunk limits"
+        );
+    }
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    fs::write(Path::new(&out_dir).join("sources.rs"), generate(&config)).unwrap();
+}
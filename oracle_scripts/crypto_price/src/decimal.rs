@@ -0,0 +1,197 @@
+use anyhow::{bail, Result};
+use std::str::FromStr;
+
+const DECIMALS: u32 = 18;
+const SCALE: u128 = 10u128.pow(DECIMALS);
+
+/// Non-negative fixed-point number with 18 decimal places
+///
+/// Values are parsed from their string representation without going through
+/// floating point, so that every validator aggregates the exact same values.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Decimal(u128);
+
+impl Decimal {
+    pub const ZERO: Decimal = Decimal(0);
+
+    /// Creates a decimal from its value scaled by 10^18
+    pub const fn from_raw(raw: u128) -> Self {
+        Decimal(raw)
+    }
+
+    /// Returns the value scaled by 10^18
+    pub fn raw(&self) -> u128 {
+        self.0
+    }
+
+    pub fn checked_add(self, other: Decimal) -> Option<Decimal> {
+        self.0.checked_add(other.0).map(Decimal)
+    }
+
+    pub fn saturating_add(self, other: Decimal) -> Decimal {
+        Decimal(self.0.saturating_add(other.0))
+    }
+
+    pub fn saturating_sub(self, other: Decimal) -> Decimal {
+        Decimal(self.0.saturating_sub(other.0))
+    }
+
+    pub fn abs_diff(self, other: Decimal) -> Decimal {
+        Decimal(self.0.abs_diff(other.0))
+    }
+
+    pub fn checked_mul_int(self, n: u64) -> Option<Decimal> {
+        self.0.checked_mul(n as u128).map(Decimal)
+    }
+
+    /// Divides by an integer, rounding down
+    pub fn checked_div_int(self, n: u64) -> Option<Decimal> {
+        self.0.checked_div(n as u128).map(Decimal)
+    }
+
+    /// Computes `self * numerator / denominator`, rounding down
+    pub fn mul_div(self, numerator: u64, denominator: u64) -> Option<Decimal> {
+        self.checked_mul_int(numerator)?
+            .checked_div_int(denominator)
+    }
+
+    /// Returns the average of two decimals, rounding down
+    pub fn midpoint(self, other: Decimal) -> Decimal {
+        Decimal(self.0 / 2 + other.0 / 2 + (self.0 % 2 + other.0 % 2) / 2)
+    }
+
+    /// Converts the decimal to an integer scaled by the given multiplier, rounding down
+    pub fn to_scaled(self, multiplier: u64) -> Option<u64> {
+        let integer = (self.0 / SCALE).checked_mul(multiplier as u128)?;
+        let fraction = self.0 % SCALE * multiplier as u128 / SCALE;
+        u64::try_from(integer.checked_add(fraction)?).ok()
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = anyhow::Error;
+
+    /// Parses a non-negative decimal in plain or scientific notation, e.g. `0.5` or `1e-9`
+    ///
+    /// Digits beyond the 18th decimal place are truncated.
+    fn from_str(s: &str) -> Result<Self> {
+        let (mantissa, exponent) = match s.split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>()?),
+            None => (s, 0),
+        };
+        let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if integer.is_empty() && fraction.is_empty()
+            || !integer
+                .chars()
+                .chain(fraction.chars())
+                .all(|c| c.is_ascii_digit())
+        {
+            bail!("Invalid decimal");
+        }
+
+        // The value is the digits shifted by the exponent and the fraction length
+        let digits = format!("{integer}{fraction}");
+        let digits = digits.trim_start_matches('0');
+        let shift = exponent as i64 - fraction.len() as i64 + DECIMALS as i64;
+        let raw = if shift >= 0 {
+            if digits.is_empty() {
+                0
+            } else {
+                let Some(factor) = u32::try_from(shift)
+                    .ok()
+                    .and_then(|shift| 10u128.checked_pow(shift))
+                else {
+                    bail!("Decimal overflow");
+                };
+                let Some(raw) = digits.parse::<u128>()?.checked_mul(factor) else {
+                    bail!("Decimal overflow");
+                };
+                raw
+            }
+        } else {
+            let kept = digits.len().saturating_sub(shift.unsigned_abs() as usize);
+            match &digits[..kept] {
+                "" => 0,
+                digits => digits.parse::<u128>()?,
+            }
+        };
+
+        Ok(Decimal(raw))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(dec("1"), Decimal(SCALE));
+        assert_eq!(dec("1.5"), Decimal(15 * SCALE / 10));
+        assert_eq!(dec("0.000000001"), Decimal(1_000_000_000));
+        assert_eq!(dec("1e-9"), Decimal(1_000_000_000));
+        assert_eq!(dec("1.5E3"), Decimal(1500 * SCALE));
+        assert_eq!(dec(".5"), Decimal(SCALE / 2));
+        assert_eq!(dec("2."), Decimal(2 * SCALE));
+        assert_eq!(dec("0000.000"), Decimal::ZERO);
+        assert_eq!(dec("0e999"), Decimal::ZERO);
+        assert_eq!(
+            dec("123456789012345.123456789"),
+            Decimal(123456789012345123456789000000000)
+        );
+
+        // Test smallest unit and truncation cases
+        assert_eq!(dec("1e-18"), Decimal(1));
+        assert_eq!(dec("0.0000000000000000019"), Decimal(1));
+        assert_eq!(dec("1e-19"), Decimal::ZERO);
+
+        // Test invalid cases
+        for invalid in [
+            "", ".", "-1", "+1", "abc", "NaN", "inf", "1.2.3", "1e", "1e1.5",
+        ] {
+            assert!(invalid.parse::<Decimal>().is_err(), "{invalid}");
+        }
+
+        // Test overflow cases
+        assert!("1e21".parse::<Decimal>().is_err());
+        assert!("1e99999999999".parse::<Decimal>().is_err());
+        assert!("340282366920938463463.374607431768211456"
+            .parse::<Decimal>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_to_scaled() {
+        assert_eq!(dec("1.25").to_scaled(1_000_000_000), Some(1_250_000_000));
+        assert_eq!(dec("1e-9").to_scaled(1_000_000_000), Some(1));
+        assert_eq!(dec("1e-10").to_scaled(1_000_000_000), Some(0));
+        assert_eq!(
+            dec("12345678901.123456789").to_scaled(1_000_000_000),
+            Some(12345678901123456789)
+        );
+        assert_eq!(dec("100000000000000000000").to_scaled(1_000_000_000), None);
+        assert_eq!(
+            dec("18.446744073709551615").to_scaled(1_000_000_000_000_000_000),
+            Some(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(dec("1").midpoint(dec("2")), dec("1.5"));
+        assert_eq!(Decimal(1).midpoint(Decimal(2)), Decimal(1));
+        assert_eq!(
+            Decimal(u128::MAX).midpoint(Decimal(u128::MAX)),
+            Decimal(u128::MAX)
+        );
+        assert_eq!(dec("1.5").abs_diff(dec("2")), dec("0.5"));
+        assert_eq!(dec("1").saturating_sub(dec("2")), Decimal::ZERO);
+        assert_eq!(dec("3").mul_div(1, 3), Some(dec("1")));
+        assert_eq!(dec("1").mul_div(1, 0), None);
+        assert_eq!(Decimal(u128::MAX).checked_mul_int(2), None);
+    }
+}
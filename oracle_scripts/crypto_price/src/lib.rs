@@ -0,0 +1,2908 @@
+use anyhow::{bail, Result};
+use num::Integer;
+use std::collections::{BTreeMap, HashMap};
+use std::iter::zip;
+use std::{fmt, io};
+
+use obi::{OBIDecode, OBIEncode, OBISchema};
+#[cfg(not(test))]
+use owasm_kit::{ext, oei};
+
+use crate::decimal::Decimal;
+#[cfg(test)]
+use crate::mock::{ext, oei};
+
+mod decimal;
+#[cfg(test)]
+mod mock;
+
+// Generated by the build script from `symbols.toml`
+include!(concat!(env!("OUT_DIR"), "/sources.rs"));
+
+const USD: &str = "USD";
+const DEFAULT_DECIMALS: u8 = 9;
+const TRIMMED_MEAN_PERCENTAGE: usize = 20;
+const DEFAULT_VALIDATOR_OUTLIER_FILTER: OutlierFilter = OutlierFilter::Mad(3);
+const DEFAULT_DATA_SOURCE_OUTLIER_FILTER: OutlierFilter = OutlierFilter::Band(1000);
+const STRUCTURED_OUTPUT_PREFIX: &str = "v2;";
+const MAX_REPORT_AGE: i64 = 5 * 60;
+const MAX_CLOCK_SKEW: i64 = 60;
+const VOLUME_WEIGHT_SCALE: u128 = 1000;
+const CHUNK_EXTERNAL_ID_OFFSET: i64 = 1 << 32;
+// Requests with at most this many unique symbols fit in the raw request budget even if every
+// symbol was asked to every data source
+const MAX_SYMBOL_COUNT: usize = MAX_RAW_REQUEST_COUNT / DATA_SOURCE_COUNT * MAX_CHUNK_SYMBOLS;
+const MAX_SYMBOL_LENGTH: usize = 16;
+
+/// Network the oracle script is deployed on, which determines the data source ids
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Network {
+    Mainnet,
+    Testnet,
+}
+
+#[derive(OBISchema)]
+#[cfg_attr(test, derive(OBIEncode, Clone))]
+pub struct Input {
+    // Symbols are requested either as `BASE/QUOTE` pairs or as a plain `BASE` quoted in USD
+    symbols: Vec<String>,
+    minimum_source_count: u8,
+    // Optional fields which take their default value when absent from the calldata
+    decimals: u8,
+    aggregation_method: u8,
+    max_spread_bps: u32,
+    include_diagnostics: bool,
+    max_band_bps: u32,
+    // Outlier filters given as their kind and threshold
+    validator_outlier_filter: u8,
+    validator_outlier_threshold: u64,
+    data_source_outlier_filter: u8,
+    data_source_outlier_threshold: u64,
+    check_wrapped: bool,
+}
+
+impl OBIDecode for Input {
+    fn decode(buf: &mut &[u8]) -> io::Result<Self> {
+        Ok(Input {
+            symbols: OBIDecode::decode(buf)?,
+            minimum_source_count: OBIDecode::decode(buf)?,
+            decimals: decode_or(buf, DEFAULT_DECIMALS)?,
+            aggregation_method: decode_or(buf, AggregationMethod::Median as u8)?,
+            max_spread_bps: decode_or(buf, 0)?,
+            include_diagnostics: decode_or(buf, false)?,
+            max_band_bps: decode_or(buf, 0)?,
+            validator_outlier_filter: decode_or(buf, DEFAULT_VALIDATOR_OUTLIER_FILTER.kind())?,
+            validator_outlier_threshold: decode_or(
+                buf,
+                DEFAULT_VALIDATOR_OUTLIER_FILTER.threshold(),
+            )?,
+            data_source_outlier_filter: decode_or(buf, DEFAULT_DATA_SOURCE_OUTLIER_FILTER.kind())?,
+            data_source_outlier_threshold: decode_or(
+                buf,
+                DEFAULT_DATA_SOURCE_OUTLIER_FILTER.threshold(),
+            )?,
+            check_wrapped: decode_or(buf, false)?,
+        })
+    }
+}
+
+impl Input {
+    /// Checks that the input can be served, returning its aggregation parameters
+    ///
+    /// The symbol limit applies to the unique symbols of the requested pairs, other than USD, so
+    /// that pairs between the same symbols do not count against it.
+    fn validate(&self) -> Result<AggregationParams> {
+        let pairs = self.pairs();
+        let mut symbols = pairs
+            .iter()
+            .flat_map(|p| [p.base.as_str(), p.quote.as_str()])
+            .filter(|s| *s != USD)
+            .collect::<Vec<&str>>();
+        symbols.sort_unstable();
+        symbols.dedup();
+        if symbols.len() > MAX_SYMBOL_COUNT {
+            bail!("Too many symbols");
+        }
+        self.aggregation_params()
+    }
+
+    /// Returns the requested pairs without duplicates, in the order they were first requested
+    fn pairs(&self) -> Vec<Pair> {
+        let mut pairs: Vec<Pair> = Vec::with_capacity(self.symbols.len());
+        for symbol in &self.symbols {
+            let pair = Pair::parse(symbol);
+            if !pairs.contains(&pair) {
+                pairs.push(pair);
+            }
+        }
+        pairs
+    }
+
+    /// Returns the aggregation parameters requested by the input
+    fn aggregation_params(&self) -> Result<AggregationParams> {
+        let Some(multiplier) = 10u64.checked_pow(self.decimals as u32) else {
+            bail!("Invalid decimals");
+        };
+
+        Ok(AggregationParams {
+            minimum_source_count: self.minimum_source_count as usize,
+            multiplier,
+            method: AggregationMethod::try_from(self.aggregation_method)?,
+            max_spread_bps: self.max_spread_bps,
+            max_band_bps: self.max_band_bps,
+            validator_outlier_filter: OutlierFilter::new(
+                self.validator_outlier_filter,
+                self.validator_outlier_threshold,
+            )?,
+            data_source_outlier_filter: OutlierFilter::new(
+                self.data_source_outlier_filter,
+                self.data_source_outlier_threshold,
+            )?,
+            check_wrapped: self.check_wrapped,
+        })
+    }
+}
+
+/// Decodes an optional trailing field, returning the default if the buffer is exhausted
+fn decode_or<T: OBIDecode>(buf: &mut &[u8], default: T) -> io::Result<T> {
+    if buf.is_empty() {
+        Ok(default)
+    } else {
+        T::decode(buf)
+    }
+}
+
+/// Method used to aggregate the data source rates of a symbol
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum AggregationMethod {
+    Median,
+    Mean,
+    TrimmedMean,
+}
+
+impl TryFrom<u8> for AggregationMethod {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(AggregationMethod::Median),
+            1 => Ok(AggregationMethod::Mean),
+            2 => Ok(AggregationMethod::TrimmedMean),
+            _ => bail!("Invalid aggregation method"),
+        }
+    }
+}
+
+impl AggregationMethod {
+    /// Aggregates the given rate and weight pairs
+    fn aggregate(&self, mut rates: Vec<(Decimal, u64)>) -> Option<Decimal> {
+        match self {
+            AggregationMethod::Median => weighted_median(rates),
+            AggregationMethod::Mean => weighted_mean(&rates),
+            AggregationMethod::TrimmedMean => {
+                rates.sort_by_key(|(r, _)| *r);
+                let trim = rates.len() * TRIMMED_MEAN_PERCENTAGE / 100;
+                weighted_mean(&rates[trim..rates.len() - trim])
+            }
+        }
+    }
+}
+
+/// Parameters controlling how the data source rates of a symbol are aggregated
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct AggregationParams {
+    minimum_source_count: usize,
+    multiplier: u64,
+    method: AggregationMethod,
+    /// Maximum spread between the lowest and highest rate in basis points, or zero for no limit
+    max_spread_bps: u32,
+    /// Maximum width of the confidence band relative to the rate in basis points, or zero for no
+    /// limit
+    max_band_bps: u32,
+    /// Filter applied to the rates reported by the validators for a data source
+    validator_outlier_filter: OutlierFilter,
+    /// Filter applied to the rates of the data sources of a symbol
+    data_source_outlier_filter: OutlierFilter,
+    /// Whether wrapped rates are cross-checked against the rate of their underlying symbol
+    check_wrapped: bool,
+}
+
+impl AggregationParams {
+    /// Returns the parameters to use for the given symbol
+    fn for_symbol(&self, symbol: &str) -> AggregationParams {
+        match SYMBOLS.get(symbol) {
+            Some(info) => AggregationParams {
+                minimum_source_count: info.minimum_source_count(self.minimum_source_count),
+                ..*self
+            },
+            None => *self,
+        }
+    }
+
+    #[cfg(test)]
+    fn new(minimum_source_count: usize) -> Self {
+        AggregationParams {
+            minimum_source_count,
+            multiplier: 10u64.pow(DEFAULT_DECIMALS as u32),
+            method: AggregationMethod::Median,
+            max_spread_bps: 0,
+            max_band_bps: 0,
+            validator_outlier_filter: DEFAULT_VALIDATOR_OUTLIER_FILTER,
+            data_source_outlier_filter: DEFAULT_DATA_SOURCE_OUTLIER_FILTER,
+            check_wrapped: false,
+        }
+    }
+
+    /// Checks whether the spread of the given rates exceeds the maximum spread
+    ///
+    /// Spreads too large to be computed are considered to exceed the maximum spread.
+    fn exceeds_max_spread(&self, rates: &[(Decimal, u64)]) -> bool {
+        if self.max_spread_bps == 0 {
+            return false;
+        }
+
+        let (Some(min), Some(max)) = (
+            rates.iter().map(|(r, _)| *r).min(),
+            rates.iter().map(|(r, _)| *r).max(),
+        ) else {
+            return false;
+        };
+        match (
+            max.abs_diff(min).checked_mul_int(10_000),
+            min.checked_mul_int(self.max_spread_bps as u64),
+        ) {
+            (Some(spread), Some(limit)) => spread > limit,
+            _ => true,
+        }
+    }
+
+    /// Checks whether the confidence band of a rate is wider than the maximum band width
+    ///
+    /// Widths too large to be computed are considered to exceed the maximum width.
+    fn exceeds_max_band(&self, rate: Decimal, low: Decimal, high: Decimal) -> bool {
+        if self.max_band_bps == 0 {
+            return false;
+        }
+
+        match (
+            high.abs_diff(low).checked_mul_int(10_000),
+            rate.checked_mul_int(self.max_band_bps as u64),
+        ) {
+            (Some(width), Some(limit)) => width > limit,
+            _ => true,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum ResponseCode {
+    Success,
+    SymbolNotSupported,
+    NotEnoughSources,
+    ConversionError,
+    TooManyOutliers,
+    SpreadTooWide,
+    FallbackSuccess,
+    HighDispersion,
+    PegDeviation,
+    ReferenceUnavailable,
+    InvalidSymbol,
+    Unknown = 127,
+}
+
+#[derive(OBIEncode, OBISchema, PartialEq, Debug)]
+struct Response {
+    symbol: String,
+    response_code: u8,
+    rate: u64,
+    quote_symbol: String,
+    // Confidence band of the rate
+    rate_low: u64,
+    rate_high: u64,
+}
+
+impl Response {
+    fn new(pair: &Pair, response_code: ResponseCode, rate: Rate) -> Self {
+        Response {
+            symbol: pair.base.clone(),
+            response_code: response_code as u8,
+            rate: rate.value,
+            quote_symbol: pair.quote.clone(),
+            rate_low: rate.low,
+            rate_high: rate.high,
+        }
+    }
+}
+
+/// Scaled rate along with its confidence band
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+struct Rate {
+    value: u64,
+    low: u64,
+    high: u64,
+}
+
+impl Rate {
+    fn exact(value: u64) -> Self {
+        Rate {
+            value,
+            low: value,
+            high: value,
+        }
+    }
+
+    /// Multiplies by another rate scaled by the given multiplier, saturating the upper bound
+    fn mul(&self, other: &Rate, multiplier: u64) -> Result<Rate, ResponseCode> {
+        Ok(Rate {
+            value: mul_div(self.value, other.value, multiplier)?,
+            low: mul_div(self.low, other.low, multiplier)?,
+            high: mul_div(self.high, other.high, multiplier).unwrap_or(u64::MAX),
+        })
+    }
+
+    /// Divides by another rate scaled by the given multiplier, saturating the upper bound
+    fn div(&self, other: &Rate, multiplier: u64) -> Result<Rate, ResponseCode> {
+        Ok(Rate {
+            value: mul_div(self.value, multiplier, other.value)?,
+            low: mul_div(self.low, multiplier, other.high)?,
+            high: mul_div(self.high, multiplier, other.low).unwrap_or(u64::MAX),
+        })
+    }
+}
+
+/// Base and quote symbols of a requested rate
+#[derive(Debug, Clone, PartialEq)]
+struct Pair {
+    base: String,
+    quote: String,
+}
+
+impl Pair {
+    /// Parses a requested symbol given either as `BASE/QUOTE` or as `BASE` for a USD quote
+    fn parse(symbol: &str) -> Self {
+        match symbol.split_once('/') {
+            Some((base, quote)) => Pair {
+                base: base.to_string(),
+                quote: quote.to_string(),
+            },
+            None => Pair {
+                base: symbol.to_string(),
+                quote: USD.to_string(),
+            },
+        }
+    }
+
+    /// Checks whether both symbols of the pair are non-empty and at most `MAX_SYMBOL_LENGTH`
+    /// bytes long
+    fn is_valid(&self) -> bool {
+        [&self.base, &self.quote]
+            .iter()
+            .all(|s| !s.is_empty() && s.len() <= MAX_SYMBOL_LENGTH)
+    }
+}
+
+#[derive(OBIEncode, OBISchema, PartialEq, Debug)]
+pub struct Output {
+    responses: Vec<Response>,
+    // Always encoded, but left empty unless requested by the input
+    diagnostics: Vec<SymbolDiagnostics>,
+    data_source_diagnostics: Vec<DataSourceDiagnostics>,
+}
+
+/// Median rate reported for a symbol by a data source, scaled by the requested multiplier
+#[derive(OBIEncode, OBISchema, PartialEq, Debug)]
+struct SourceDiagnostics {
+    data_source_id: i64,
+    rate: u64,
+}
+
+/// Data sources which contributed a rate to a symbol
+#[derive(OBIEncode, OBISchema, PartialEq, Debug)]
+struct SymbolDiagnostics {
+    symbol: String,
+    sources: Vec<SourceDiagnostics>,
+}
+
+/// Number of validator outputs of a data source rejected for each reason
+#[derive(OBIEncode, OBISchema, PartialEq, Debug)]
+struct DataSourceDiagnostics {
+    data_source_id: i64,
+    mismatched_length: u32,
+    negative_value: u32,
+    invalid_value: u32,
+}
+
+impl DataSourceDiagnostics {
+    fn new(data_source_id: i64) -> Self {
+        DataSourceDiagnostics {
+            data_source_id,
+            mismatched_length: 0,
+            negative_value: 0,
+            invalid_value: 0,
+        }
+    }
+
+    /// Counts a rejected output by the reason of its error
+    fn record(&mut self, err: &anyhow::Error) {
+        match err.downcast_ref::<OutputError>() {
+            Some(OutputError::MismatchedLength) => self.mismatched_length += 1,
+            Some(OutputError::NegativeValue) => self.negative_value += 1,
+            None => self.invalid_value += 1,
+        }
+    }
+}
+
+/// Filter used to discard outliers before medianizing
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum OutlierFilter {
+    /// Keeps values within the given number of median absolute deviations from the median
+    Mad(u64),
+    /// Keeps values within the given basis points of the median from the median
+    Band(u64),
+}
+
+impl OutlierFilter {
+    /// Returns the filter of the given kind, `0` for MAD and `1` for band, with the given threshold
+    fn new(kind: u8, threshold: u64) -> Result<Self> {
+        match kind {
+            0 => Ok(OutlierFilter::Mad(threshold)),
+            1 => Ok(OutlierFilter::Band(threshold)),
+            _ => bail!("Invalid outlier filter"),
+        }
+    }
+
+    fn kind(&self) -> u8 {
+        match self {
+            OutlierFilter::Mad(_) => 0,
+            OutlierFilter::Band(_) => 1,
+        }
+    }
+
+    fn threshold(&self) -> u64 {
+        match self {
+            OutlierFilter::Mad(k) => *k,
+            OutlierFilter::Band(bps) => *bps,
+        }
+    }
+
+    /// Returns the range of accepted values around the preliminary median of the given values
+    fn bounds(&self, values: &[Decimal]) -> Option<(Decimal, Decimal)> {
+        let center = median(values.to_vec())?;
+        let tolerance = match self {
+            OutlierFilter::Mad(k) => {
+                let deviations = values.iter().map(|v| v.abs_diff(center)).collect();
+                median(deviations)?.checked_mul_int(*k)?
+            }
+            OutlierFilter::Band(bps) => center.mul_div(*bps, 10_000)?,
+        };
+        Some((
+            center.saturating_sub(tolerance),
+            center.saturating_add(tolerance),
+        ))
+    }
+
+    /// Removes the items whose value lies outside of the accepted range
+    ///
+    /// Items are kept as is if no range can be computed from their values.
+    fn apply<T>(&self, items: Vec<T>, value: impl Fn(&T) -> Decimal) -> Vec<T> {
+        let values = items.iter().map(&value).collect::<Vec<Decimal>>();
+        match self.bounds(&values) {
+            Some((lower, upper)) => items
+                .into_iter()
+                .filter(|item| (lower..=upper).contains(&value(item)))
+                .collect(),
+            None => items,
+        }
+    }
+}
+
+/// Data sources and aggregation settings of a supported symbol
+struct SymbolInfo {
+    sources: &'static [DataSources],
+    /// Data sources only used when too few primary sources returned a rate
+    fallback_sources: &'static [DataSources],
+    /// Symbol the data sources quote the rate in
+    quote: &'static str,
+    /// Caps the requested minimum source count for symbols with few data sources
+    minimum_source_count: Option<u8>,
+    class: SymbolClass,
+    /// Computes the rate from another symbol instead of asking data sources
+    derived: Option<Derived>,
+}
+
+/// Underlying symbol and conversion ratio of a derived symbol, such as a bridged token
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Derived {
+    underlying: &'static str,
+    ratio: Decimal,
+}
+
+/// Class of a symbol, which determines the sanity checks applied to its rate
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum SymbolClass {
+    Volatile,
+    /// Pegged to one unit of its quote
+    Stablecoin,
+    /// Tracks the rate of the given underlying symbol
+    Wrapped(&'static str),
+}
+
+impl SymbolInfo {
+    /// Returns the minimum source count applied to the symbol given the requested one
+    fn minimum_source_count(&self, requested: usize) -> usize {
+        match self.minimum_source_count {
+            Some(count) => requested.min(count as usize),
+            None => requested,
+        }
+    }
+
+    /// Returns the data sources of both tiers
+    ///
+    /// Oracle scripts cannot request more data after the prepare phase, so
+    /// fallback sources are always asked and only used if needed when executing.
+    fn all_sources(&self) -> impl Iterator<Item = &DataSources> {
+        self.sources.iter().chain(self.fallback_sources)
+    }
+
+    /// Checks whether the data source is a fallback source of the symbol
+    fn is_fallback(&self, ds: &DataSources) -> bool {
+        self.fallback_sources.contains(ds)
+    }
+}
+
+/// Rates and weights of a symbol from its primary and fallback data sources
+#[derive(Debug, Clone, PartialEq)]
+struct SymbolRates {
+    quote: &'static str,
+    primary: Vec<(Decimal, u64)>,
+    fallback: Vec<(Decimal, u64)>,
+}
+
+impl SymbolRates {
+    fn new(quote: &'static str) -> Self {
+        SymbolRates {
+            quote,
+            primary: Vec::new(),
+            fallback: Vec::new(),
+        }
+    }
+
+    /// Splits the data source rates of a symbol into its tiers
+    ///
+    /// If every data source reported a traded volume, the weight of each data source is scaled by
+    /// its volume relative to the highest one.
+    fn from_source_rates(info: &SymbolInfo, source_rates: &[SourceRate]) -> Self {
+        let max_volume = source_rates
+            .iter()
+            .map(|r| r.volume)
+            .collect::<Option<Vec<Decimal>>>()
+            .and_then(|volumes| volumes.into_iter().max())
+            .filter(|max| *max > Decimal::ZERO);
+
+        let mut rates = SymbolRates::new(info.quote);
+        for r in source_rates {
+            let weight = match (max_volume, r.volume) {
+                (Some(max), Some(volume)) => {
+                    let factor = volume.raw().saturating_mul(VOLUME_WEIGHT_SCALE) / max.raw();
+                    r.source.weight() * (factor as u64).max(1)
+                }
+                _ => r.source.weight(),
+            };
+            if info.is_fallback(&r.source) {
+                rates.fallback.push((r.rate, weight));
+            } else {
+                rates.primary.push((r.rate, weight));
+            }
+        }
+        rates
+    }
+}
+
+/// Median rate and volume of a symbol reported by the validators for a data source
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct SourceRate {
+    source: DataSources,
+    rate: Decimal,
+    volume: Option<Decimal>,
+}
+
+/// Returns the symbols whose rates are needed to compute the requested pairs
+///
+/// Symbols quoted in another symbol than USD also require the rates of their quote, while
+/// derived symbols require the rates of their underlying symbol. Wrapped symbols only require
+/// the rates of their underlying symbol when they are cross-checked against it.
+fn get_required_symbols(pairs: &[Pair], check_wrapped: bool) -> Vec<String> {
+    let mut required: Vec<String> = Vec::new();
+    for pair in pairs.iter().filter(|p| p.is_valid()) {
+        add_required_symbol(&pair.base, check_wrapped, &mut required);
+        add_required_symbol(&pair.quote, check_wrapped, &mut required);
+    }
+    required
+}
+
+fn add_required_symbol(symbol: &str, check_wrapped: bool, required: &mut Vec<String>) {
+    if symbol == USD || required.iter().any(|s| s == symbol) {
+        return;
+    }
+    required.push(symbol.to_string());
+    if let Some(info) = SYMBOLS.get(symbol) {
+        add_required_symbol(info.quote, check_wrapped, required);
+        if let (SymbolClass::Wrapped(underlying), true) = (info.class, check_wrapped) {
+            add_required_symbol(underlying, check_wrapped, required);
+        }
+        if let Some(derived) = info.derived {
+            add_required_symbol(derived.underlying, check_wrapped, required);
+        }
+    }
+}
+
+/// Returns a BTreeMap mapping the data source to its supported symbols
+///
+/// Data sources are ordered by name and their symbols as given, so that the raw requests of a
+/// given input are always issued in the same order.
+fn get_symbols_for_data_sources(symbols: &[String]) -> BTreeMap<DataSources, Vec<String>> {
+    symbols.iter().fold(BTreeMap::new(), |mut acc, symbol| {
+        if let Some(info) = SYMBOLS.get(symbol.as_str()) {
+            for ds in info.all_sources() {
+                acc.entry(*ds)
+                    .and_modify(|e| {
+                        e.push(symbol.clone());
+                    })
+                    .or_insert(vec![symbol.clone()]);
+            }
+        }
+        acc
+    })
+}
+
+/// Reason for rejecting a validator's data source output, other than values failing to parse
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum OutputError {
+    MismatchedLength,
+    NegativeValue,
+}
+
+impl fmt::Display for OutputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OutputError::MismatchedLength => write!(f, "Mismatched length"),
+            OutputError::NegativeValue => write!(f, "Negative value"),
+        }
+    }
+}
+
+impl std::error::Error for OutputError {}
+
+/// Parses a non-negative decimal, telling negative values apart from invalid ones
+fn parse_non_negative(v: &str) -> Result<Decimal> {
+    if v.starts_with('-') {
+        bail!(OutputError::NegativeValue);
+    }
+    v.parse::<Decimal>()
+}
+
+/// Raw request asking a data source for the rates of a chunk of symbols
+#[derive(Debug, PartialEq)]
+struct Ask {
+    source: DataSources,
+    external_id: i64,
+    symbols: Vec<String>,
+}
+
+/// Splits symbols into chunks of at most `max_symbols` symbols whose space separated calldata
+/// fits in `max_size` bytes
+///
+/// Symbols longer than `max_size` are asked in a chunk of their own.
+fn chunk_symbols(symbols: Vec<String>, max_symbols: usize, max_size: usize) -> Vec<Vec<String>> {
+    let mut chunks: Vec<Vec<String>> = Vec::new();
+    let mut size = 0;
+    for symbol in symbols {
+        match chunks.last_mut() {
+            Some(chunk) if chunk.len() < max_symbols && size + 1 + symbol.len() <= max_size => {
+                size += 1 + symbol.len();
+                chunk.push(symbol);
+            }
+            _ => {
+                size = symbol.len();
+                chunks.push(vec![symbol]);
+            }
+        }
+    }
+    chunks
+}
+
+/// Returns the external id of a chunk of the symbols asked to a data source
+///
+/// The first chunk uses the data source id, so that requests fitting in a single chunk keep the
+/// same external ids.
+fn external_id(data_source_id: i64, chunk: usize) -> i64 {
+    chunk as i64 * CHUNK_EXTERNAL_ID_OFFSET + data_source_id
+}
+
+/// Returns the raw requests needed to get the rates of the given symbols
+fn get_asks(symbols: &[String], network: Network) -> Vec<Ask> {
+    get_symbols_for_data_sources(symbols)
+        .into_iter()
+        .flat_map(|(source, symbols)| {
+            chunk_symbols(symbols, MAX_CHUNK_SYMBOLS, MAX_CHUNK_CALLDATA_SIZE)
+                .into_iter()
+                .enumerate()
+                .map(move |(i, symbols)| Ask {
+                    source,
+                    external_id: external_id(source.id(network), i),
+                    symbols,
+                })
+        })
+        .collect()
+}
+
+/// Parses the individual values to assure its value is usable
+fn validate_value(v: &str) -> Result<Option<Decimal>> {
+    if v == "-" {
+        Ok(None)
+    } else {
+        Ok(Some(parse_non_negative(v)?))
+    }
+}
+
+/// Validates and parses the a validator's data source output
+fn validate_and_parse_output(ds_output: &str, length: usize) -> Result<Vec<Option<Decimal>>> {
+    let values = ds_output.split(",").collect::<Vec<&str>>();
+
+    // If the length of the output is not equal to the expected length, raise an error
+    if values.len() != length {
+        bail!(OutputError::MismatchedLength);
+    }
+
+    values
+        .into_iter()
+        .map(|v| validate_value(v.trim()))
+        .collect()
+}
+
+/// A symbol's price reported by a validator, along with its timestamp and traded volume when the
+/// data source uses the structured output format
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct PriceReport {
+    price: Decimal,
+    timestamp: Option<i64>,
+    volume: Option<Decimal>,
+}
+
+impl PriceReport {
+    fn from_price(price: Decimal) -> Self {
+        PriceReport {
+            price,
+            timestamp: None,
+            volume: None,
+        }
+    }
+
+    /// Checks whether the report was made within the accepted window around the prepare time
+    ///
+    /// Reports are accepted from `MAX_REPORT_AGE` seconds before the prepare time up to
+    /// `MAX_CLOCK_SKEW` seconds after it, which covers the delay before validators run the data
+    /// sources and small clock differences.
+    fn is_timely(&self, prepare_time: i64) -> bool {
+        let window = prepare_time - MAX_REPORT_AGE..=prepare_time + MAX_CLOCK_SKEW;
+        match self.timestamp {
+            Some(timestamp) => window.contains(&timestamp),
+            None => true,
+        }
+    }
+}
+
+/// Parses an individual `price:timestamp:volume` entry of the structured output format
+fn validate_report(v: &str) -> Result<Option<PriceReport>> {
+    if v == "-" {
+        return Ok(None);
+    }
+
+    let [price, timestamp, volume] = v.split(':').collect::<Vec<&str>>()[..] else {
+        bail!("Invalid report");
+    };
+    let Some(price) = validate_value(price)? else {
+        bail!("Missing price");
+    };
+    Ok(Some(PriceReport {
+        price,
+        timestamp: Some(timestamp.parse::<i64>()?),
+        volume: Some(parse_non_negative(volume)?),
+    }))
+}
+
+/// Parses a validator's data source output in either the structured or the legacy CSV format
+///
+/// The structured format is prefixed by its version, e.g. `v2;1.22:1700000000:15000,-`, while
+/// the legacy format only lists prices, e.g. `1.22,-`.
+fn parse_output(ds_output: &str, length: usize) -> Result<Vec<Option<PriceReport>>> {
+    match ds_output.trim().strip_prefix(STRUCTURED_OUTPUT_PREFIX) {
+        Some(reports) => {
+            let reports = reports.split(",").collect::<Vec<&str>>();
+            if reports.len() != length {
+                bail!(OutputError::MismatchedLength);
+            }
+            reports
+                .into_iter()
+                .map(|v| validate_report(v.trim()))
+                .collect()
+        }
+        None => Ok(validate_and_parse_output(ds_output, length)?
+            .into_iter()
+            .map(|v| v.map(PriceReport::from_price))
+            .collect()),
+    }
+}
+
+/// Medianizes the traded volumes reported for each symbol
+fn medianize_volumes(reports: &[Vec<Option<PriceReport>>], length: usize) -> Vec<Option<Decimal>> {
+    (0..length)
+        .map(|i| {
+            let volumes = reports
+                .iter()
+                .filter_map(|o| o[i].and_then(|r| r.volume))
+                .collect::<Vec<Decimal>>();
+            median(volumes)
+        })
+        .collect()
+}
+
+/// Gets the minimum successful response required given the minimum request count
+fn get_minimum_response_count(min_count: i64) -> usize {
+    if min_count.is_even() {
+        ((min_count + 2) / 2) as usize
+    } else {
+        ((min_count + 1) / 2) as usize
+    }
+}
+
+/// Filters and medianizes the parsed data source output
+fn filter_and_medianize(
+    rates: Vec<Vec<Option<Decimal>>>,
+    length: usize,
+    min_response: usize,
+    filter: &OutlierFilter,
+) -> Vec<Option<Decimal>> {
+    (0..length)
+        .map(|i| {
+            let symbol_rates = rates.iter().filter_map(|o| o[i]).collect::<Vec<Decimal>>();
+            let symbol_rates = filter.apply(symbol_rates, |r| *r);
+            if symbol_rates.len() < min_response {
+                None
+            } else {
+                median(symbol_rates)
+            }
+        })
+        .collect::<Vec<Option<Decimal>>>()
+}
+
+/// Computes the median of the given values, averaging the two middle values of even length sets
+fn median(mut values: Vec<Decimal>) -> Option<Decimal> {
+    values.sort();
+    let mid = values.len() / 2;
+    if values.is_empty() {
+        None
+    } else if values.len().is_even() {
+        Some(values[mid - 1].midpoint(values[mid]))
+    } else {
+        Some(values[mid])
+    }
+}
+
+/// Computes the weighted percentile of the given rate and weight pairs
+///
+/// If the cumulative weight splits exactly at the percentile between two rates,
+/// their average is returned so that equal weights yield the plain median.
+fn weighted_percentile(mut rates: Vec<(Decimal, u64)>, percentile: u64) -> Option<Decimal> {
+    rates.sort_by_key(|(r, _)| *r);
+    let total_weight = rates.iter().map(|(_, w)| w).sum::<u64>();
+    let target = total_weight * percentile;
+
+    let mut cumulative_weight = 0;
+    for (i, (rate, weight)) in rates.iter().enumerate() {
+        cumulative_weight += weight;
+        if cumulative_weight * 100 == target {
+            return rates
+                .get(i + 1)
+                .map(|(next_rate, _)| rate.midpoint(*next_rate));
+        }
+        if cumulative_weight * 100 > target {
+            return Some(*rate);
+        }
+    }
+    None
+}
+
+/// Computes the weighted median of the given rate and weight pairs
+fn weighted_median(rates: Vec<(Decimal, u64)>) -> Option<Decimal> {
+    weighted_percentile(rates, 50)
+}
+
+/// Computes the weighted interquartile range of the given rate and weight pairs
+fn interquartile_range(rates: Vec<(Decimal, u64)>) -> Option<(Decimal, Decimal)> {
+    Some((
+        weighted_percentile(rates.clone(), 25)?,
+        weighted_percentile(rates, 75)?,
+    ))
+}
+
+/// Computes the weighted mean of the given rate and weight pairs, rounding down
+fn weighted_mean(rates: &[(Decimal, u64)]) -> Option<Decimal> {
+    let total_weight = rates.iter().map(|(_, w)| w).sum::<u64>();
+    if total_weight == 0 {
+        return None;
+    }
+    let weighted_sum = rates.iter().try_fold(Decimal::ZERO, |sum, (r, w)| {
+        sum.checked_add(r.checked_mul_int(*w)?)
+    })?;
+    weighted_sum.checked_div_int(total_weight)
+}
+
+/// Aggregates the weighted data sources outputs to either a result or error
+///
+/// The confidence band is the interquartile range of the rates, widened to include the
+/// aggregated rate as a mean can fall outside of it.
+fn aggregate_value(
+    rates: &[(Decimal, u64)],
+    params: &AggregationParams,
+) -> Result<Rate, ResponseCode> {
+    if rates.len() < params.minimum_source_count {
+        return Err(ResponseCode::NotEnoughSources);
+    }
+
+    let rates = params
+        .data_source_outlier_filter
+        .apply(rates.to_owned(), |(r, _)| *r);
+    if rates.len() < params.minimum_source_count {
+        Err(ResponseCode::TooManyOutliers)
+    } else if params.exceeds_max_spread(&rates) {
+        Err(ResponseCode::SpreadTooWide)
+    } else {
+        let band = interquartile_range(rates.clone());
+        if let (Some(price), Some((low, high))) = (params.method.aggregate(rates), band) {
+            let (low, high) = (low.min(price), high.max(price));
+            if params.exceeds_max_band(price, low, high) {
+                Err(ResponseCode::HighDispersion)
+            } else if let (Some(value), Some(low)) = (
+                price.to_scaled(params.multiplier),
+                low.to_scaled(params.multiplier),
+            ) {
+                Ok(Rate {
+                    value,
+                    low,
+                    high: high.to_scaled(params.multiplier).unwrap_or(u64::MAX),
+                })
+            } else {
+                Err(ResponseCode::ConversionError)
+            }
+        } else {
+            Err(ResponseCode::Unknown)
+        }
+    }
+}
+
+/// Aggregates the rates of a symbol, using its fallback sources only if too few primary sources
+/// returned a rate
+fn aggregate_symbol_rates(
+    rates: &SymbolRates,
+    params: &AggregationParams,
+) -> Result<(Rate, ResponseCode), ResponseCode> {
+    if rates.primary.len() >= params.minimum_source_count || rates.fallback.is_empty() {
+        aggregate_value(&rates.primary, params).map(|rate| (rate, ResponseCode::Success))
+    } else {
+        let rates = [rates.primary.as_slice(), rates.fallback.as_slice()].concat();
+        aggregate_value(&rates, params).map(|rate| (rate, ResponseCode::FallbackSuccess))
+    }
+}
+
+/// Computes `a * b / c` without intermediate overflow
+fn mul_div(a: u64, b: u64, c: u64) -> Result<u64, ResponseCode> {
+    (a as u128 * b as u128)
+        .checked_div(c as u128)
+        .and_then(|v| u64::try_from(v).ok())
+        .ok_or(ResponseCode::ConversionError)
+}
+
+/// Returns the code to report for a rate computed from two rates, keeping the most severe flag
+fn combine_codes(a: ResponseCode, b: ResponseCode) -> ResponseCode {
+    [
+        ResponseCode::PegDeviation,
+        ResponseCode::ReferenceUnavailable,
+        ResponseCode::FallbackSuccess,
+    ]
+    .into_iter()
+    .find(|code| a == *code || b == *code)
+    .unwrap_or(ResponseCode::Success)
+}
+
+/// Checks the rate of a symbol against the reference rate of its class
+///
+/// Wrapped symbols are only checked when requested, and are flagged with `ReferenceUnavailable`
+/// when the rate of their underlying symbol is unavailable.
+fn check_reference(
+    symbol: &str,
+    rate: u64,
+    symbol_prices: &HashMap<String, SymbolRates>,
+    params: &AggregationParams,
+) -> ResponseCode {
+    let (reference, max_deviation_bps) = match SYMBOLS.get(symbol).map(|info| info.class) {
+        Some(SymbolClass::Stablecoin) => (params.multiplier, STABLECOIN_MAX_DEVIATION_BPS),
+        Some(SymbolClass::Wrapped(underlying)) if params.check_wrapped => {
+            match get_native_rate(underlying, symbol_prices, params) {
+                Ok((underlying_rate, _)) => (underlying_rate.value, WRAPPED_MAX_DEVIATION_BPS),
+                Err(_) => return ResponseCode::ReferenceUnavailable,
+            }
+        }
+        _ => return ResponseCode::Success,
+    };
+    if rate.abs_diff(reference) as u128 * 10_000 > reference as u128 * max_deviation_bps as u128 {
+        ResponseCode::PegDeviation
+    } else {
+        ResponseCode::Success
+    }
+}
+
+/// Computes the rate of a symbol in the quote of its data sources
+fn get_native_rate(
+    symbol: &str,
+    symbol_prices: &HashMap<String, SymbolRates>,
+    params: &AggregationParams,
+) -> Result<(Rate, ResponseCode), ResponseCode> {
+    let rates = symbol_prices
+        .get(symbol)
+        .ok_or(ResponseCode::SymbolNotSupported)?;
+    if let Some(derived) = SYMBOLS.get(symbol).and_then(|info| info.derived) {
+        let (rate, code) = get_native_rate(derived.underlying, symbol_prices, params)?;
+        let ratio = derived
+            .ratio
+            .to_scaled(params.multiplier)
+            .ok_or(ResponseCode::ConversionError)?;
+        return Ok((rate.mul(&Rate::exact(ratio), params.multiplier)?, code));
+    }
+
+    let (rate, code) = aggregate_symbol_rates(rates, &params.for_symbol(symbol))?;
+    let reference_code = check_reference(symbol, rate.value, symbol_prices, params);
+    Ok((rate, combine_codes(code, reference_code)))
+}
+
+/// Computes the USD rate of a symbol by following the quotes of its data sources
+fn get_usd_rate(
+    symbol: &str,
+    symbol_prices: &HashMap<String, SymbolRates>,
+    params: &AggregationParams,
+) -> Result<(Rate, ResponseCode), ResponseCode> {
+    if symbol == USD {
+        return Ok((Rate::exact(params.multiplier), ResponseCode::Success));
+    }
+
+    let (rate, code) = get_native_rate(symbol, symbol_prices, params)?;
+    let quote = symbol_prices[symbol].quote;
+    if quote == USD {
+        return Ok((rate, code));
+    }
+
+    let (quote_rate, quote_code) = get_usd_rate(quote, symbol_prices, params)?;
+    Ok((
+        rate.mul(&quote_rate, params.multiplier)?,
+        combine_codes(code, quote_code),
+    ))
+}
+
+/// Computes the rate of a requested pair
+///
+/// Pairs quoted in the same symbol as the base's data sources are returned as is, while other
+/// pairs are computed as the cross rate of their USD rates.
+fn get_pair_rate(
+    pair: &Pair,
+    symbol_prices: &HashMap<String, SymbolRates>,
+    params: &AggregationParams,
+) -> Result<(Rate, ResponseCode), ResponseCode> {
+    if !pair.is_valid() {
+        return Err(ResponseCode::InvalidSymbol);
+    }
+
+    if symbol_prices
+        .get(&pair.base)
+        .is_some_and(|rates| rates.quote == pair.quote)
+    {
+        return get_native_rate(&pair.base, symbol_prices, params);
+    }
+
+    let (base_rate, base_code) = get_usd_rate(&pair.base, symbol_prices, params)?;
+    let (quote_rate, quote_code) = get_usd_rate(&pair.quote, symbol_prices, params)?;
+    Ok((
+        base_rate.div(&quote_rate, params.multiplier)?,
+        combine_codes(base_code, quote_code),
+    ))
+}
+
+/// Gets the oracle script responses
+fn get_responses(
+    pairs: &[Pair],
+    symbol_prices: HashMap<String, SymbolRates>,
+    params: &AggregationParams,
+) -> Vec<Response> {
+    pairs
+        .iter()
+        .map(|pair| match get_pair_rate(pair, &symbol_prices, params) {
+            Ok((rate, code)) => Response::new(pair, code, rate),
+            Err(code) => Response::new(pair, code, Rate::default()),
+        })
+        .collect()
+}
+
+/// Lists the data sources which contributed a rate to each of the given symbols, ordered by id
+///
+/// Rates too large to be scaled are reported as `u64::MAX`.
+fn get_diagnostics(
+    symbols: &[String],
+    source_rates: &HashMap<String, Vec<SourceRate>>,
+    network: Network,
+    params: &AggregationParams,
+) -> Vec<SymbolDiagnostics> {
+    symbols
+        .iter()
+        .map(|symbol| {
+            let mut sources = source_rates
+                .get(symbol)
+                .map(Vec::as_slice)
+                .unwrap_or_default()
+                .iter()
+                .map(|r| SourceDiagnostics {
+                    data_source_id: r.source.id(network),
+                    rate: r.rate.to_scaled(params.multiplier).unwrap_or(u64::MAX),
+                })
+                .collect::<Vec<SourceDiagnostics>>();
+            sources.sort_by_key(|s| s.data_source_id);
+            SymbolDiagnostics {
+                symbol: symbol.clone(),
+                sources,
+            }
+        })
+        .collect()
+}
+
+/// Asks the data sources of the given network for the rates of the requested symbols
+pub fn prepare(input: Input, network: Network) {
+    // Rejects requests with too many symbols or invalid aggregation parameters before asking
+    // for any data
+    let params = input.validate().expect("Invalid oracle script input");
+    let asks = get_asks(
+        &get_required_symbols(&input.pairs(), params.check_wrapped),
+        network,
+    );
+    assert!(asks.len() <= MAX_RAW_REQUEST_COUNT, "Too many raw requests");
+    for ask in asks {
+        oei::ask_external_data(
+            ask.external_id,
+            ask.source.id(network),
+            ask.symbols.join(" ").as_bytes(),
+        )
+    }
+}
+
+/// Aggregates the validators' reports of the given network into the requested rates
+pub fn execute(input: Input, network: Network) -> Output {
+    let params = input
+        .aggregation_params()
+        .expect("Input already validated in the prepare phase");
+    let pairs = input.pairs();
+    let required_symbols = get_required_symbols(&pairs, params.check_wrapped);
+
+    // HashMap containing all symbols and the median rate and volume from each data source
+    let mut source_rates: HashMap<String, Vec<SourceRate>> =
+        HashMap::with_capacity(required_symbols.len());
+
+    // Gets the minimum required response count
+    let min_resp_count = get_minimum_response_count(oei::get_min_count());
+
+    // Reports made too long before or after the request are discarded
+    let prepare_time = oei::get_prepare_time();
+
+    // Validator outputs rejected by each data source
+    let mut data_source_diagnostics: HashMap<DataSources, DataSourceDiagnostics> = HashMap::new();
+
+    // Chunks of symbols asked to the same data source are handled independently
+    for Ask {
+        source: ds,
+        external_id,
+        symbols,
+    } in get_asks(&required_symbols, network)
+    {
+        // Parses the validator's responses from a raw string, discarding stale reports and
+        // counting the rejected outputs
+        let ds_diagnostics = data_source_diagnostics
+            .entry(ds)
+            .or_insert_with(|| DataSourceDiagnostics::new(ds.id(network)));
+        let ds_outputs = ext::load_input::<String>(external_id)
+            .filter_map(|r| match parse_output(&r, symbols.len()) {
+                Ok(reports) => Some(reports),
+                Err(err) => {
+                    ds_diagnostics.record(&err);
+                    None
+                }
+            })
+            .map(|reports| {
+                reports
+                    .into_iter()
+                    .map(|r| r.filter(|r| r.is_timely(prepare_time)))
+                    .collect()
+            })
+            .collect::<Vec<Vec<Option<PriceReport>>>>();
+
+        // Gets data source median rates and volumes
+        let prices = ds_outputs
+            .iter()
+            .map(|o| o.iter().map(|r| r.map(|r| r.price)).collect())
+            .collect::<Vec<Vec<Option<Decimal>>>>();
+        let median_rates = filter_and_medianize(
+            prices,
+            symbols.len(),
+            min_resp_count,
+            &params.validator_outlier_filter,
+        );
+        let median_volumes = medianize_volumes(&ds_outputs, symbols.len());
+
+        // Saves symbol rates
+        for ((symbol, opt_rate), volume) in zip(zip(symbols, median_rates), median_volumes) {
+            if let Some(rate) = opt_rate {
+                source_rates.entry(symbol).or_default().push(SourceRate {
+                    source: ds,
+                    rate,
+                    volume,
+                });
+            }
+        }
+    }
+
+    // HashMap containing all supported symbols and their prices and weights from each data source tier
+    let symbol_prices = required_symbols
+        .iter()
+        .filter_map(|s| {
+            let info = SYMBOLS.get(s.as_str())?;
+            let rates = source_rates.get(s).map(Vec::as_slice).unwrap_or_default();
+            Some((s.clone(), SymbolRates::from_source_rates(info, rates)))
+        })
+        .collect::<HashMap<String, SymbolRates>>();
+
+    let (diagnostics, data_source_diagnostics) = if input.include_diagnostics {
+        let mut data_source_diagnostics = data_source_diagnostics.into_values().collect::<Vec<_>>();
+        data_source_diagnostics.sort_by_key(|d| d.data_source_id);
+        (
+            get_diagnostics(&required_symbols, &source_rates, network, &params),
+            data_source_diagnostics,
+        )
+    } else {
+        (Vec::new(), Vec::new())
+    };
+
+    Output {
+        responses: get_responses(&pairs, symbol_prices, &params),
+        diagnostics,
+        data_source_diagnostics,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{asked_external_data, MockEnv};
+    use std::collections::HashSet;
+
+    fn dec(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_decode_input() {
+        #[derive(OBIEncode)]
+        struct LegacyInput {
+            symbols: Vec<String>,
+            minimum_source_count: u8,
+        }
+
+        // Test input without optional fields case
+        let calldata = LegacyInput {
+            symbols: vec!["ETH".to_string()],
+            minimum_source_count: 3,
+        }
+        .try_to_vec()
+        .unwrap();
+        let input = Input::try_from_slice(&calldata).unwrap();
+        assert_eq!(
+            input.aggregation_params().unwrap(),
+            AggregationParams::new(3)
+        );
+        assert!(!input.include_diagnostics);
+
+        // Test input with optional fields case
+        let calldata = Input {
+            symbols: vec!["ETH".to_string()],
+            minimum_source_count: 3,
+            decimals: 18,
+            aggregation_method: 2,
+            max_spread_bps: 500,
+            include_diagnostics: true,
+            max_band_bps: 1000,
+            validator_outlier_filter: 1,
+            validator_outlier_threshold: 500,
+            data_source_outlier_filter: 0,
+            data_source_outlier_threshold: 4,
+            check_wrapped: true,
+        }
+        .try_to_vec()
+        .unwrap();
+        let input = Input::try_from_slice(&calldata).unwrap();
+        assert_eq!(
+            input.aggregation_params().unwrap(),
+            AggregationParams {
+                minimum_source_count: 3,
+                multiplier: 1_000_000_000_000_000_000,
+                method: AggregationMethod::TrimmedMean,
+                max_spread_bps: 500,
+                max_band_bps: 1000,
+                validator_outlier_filter: OutlierFilter::Band(500),
+                data_source_outlier_filter: OutlierFilter::Mad(4),
+                check_wrapped: true,
+            }
+        );
+        assert!(input.include_diagnostics);
+
+        // Test invalid parameters case
+        let mut input = Input::try_from_slice(&calldata).unwrap();
+        input.decimals = 20;
+        assert!(input.aggregation_params().is_err());
+        input.decimals = 9;
+        input.aggregation_method = 3;
+        assert!(input.aggregation_params().is_err());
+        input.aggregation_method = 0;
+        input.data_source_outlier_filter = 2;
+        assert!(input.aggregation_params().is_err());
+    }
+
+    #[test]
+    fn test_data_source_ids() {
+        assert_eq!(DataSources::BINANCE.id(Network::Mainnet), 54);
+        assert_eq!(DataSources::BINANCE.id(Network::Testnet), 230);
+        assert_eq!(DataSources::BINANCE.weight(), 3);
+
+        let eth = SYMBOLS.get("ETH").unwrap();
+        assert_eq!(eth.sources.len(), 6);
+        assert!(SYMBOLS.get("DNE").is_none());
+    }
+
+    #[test]
+    fn test_data_source_registry() {
+        assert_eq!(DataSources::ALL.len(), DATA_SOURCE_COUNT);
+        assert_eq!(DataSources::BINANCE.name(), "BINANCE");
+
+        // Ids are unique on each network
+        for network in [Network::Mainnet, Network::Testnet] {
+            let ids = DataSources::ALL
+                .iter()
+                .map(|ds| ds.id(network))
+                .collect::<HashSet<i64>>();
+            assert_eq!(ids.len(), DATA_SOURCE_COUNT);
+        }
+
+        // Every data source used by a symbol is registered
+        let used = SYMBOLS
+            .values()
+            .flat_map(|info| info.all_sources())
+            .copied()
+            .collect::<HashSet<DataSources>>();
+        assert!(used.iter().all(|ds| DataSources::ALL.contains(ds)));
+
+        // Every registered data source is used by a symbol
+        for ds in DataSources::ALL {
+            assert!(used.contains(&ds), "{} is unused", ds.name());
+        }
+    }
+
+    #[test]
+    fn test_validate_value() {
+        // Test normal case
+        let value = validate_value("0.12345").unwrap();
+        assert_eq!(value, Some(dec("0.12345")));
+
+        // Test null case
+        let null_value = validate_value("-").unwrap();
+        assert_eq!(null_value, None);
+
+        // Test negative case
+        let failed_value = validate_value("-0.555");
+        assert!(failed_value.is_err());
+
+        // Test failed case
+        let failed_value = validate_value("abc");
+        assert!(failed_value.is_err());
+    }
+
+    #[test]
+    fn test_validate_and_parse_output() {
+        // Test normal case
+        let ds_outputs = "1.22,1.32,1.44".to_string();
+        let parsed_output = validate_and_parse_output(&ds_outputs, 3).unwrap();
+        let expected_output = vec![Some(dec("1.22")), Some(dec("1.32")), Some(dec("1.44"))];
+        assert_eq!(parsed_output, expected_output);
+
+        // Test normal bad format case
+        let ds_outputs = "1.22, 1.32, 1.44".to_string();
+        let parsed_output = validate_and_parse_output(&ds_outputs, 3).unwrap();
+        let expected_output = vec![Some(dec("1.22")), Some(dec("1.32")), Some(dec("1.44"))];
+        assert_eq!(parsed_output, expected_output);
+
+        // Test contains null case
+        let ds_outputs = "1.22,1.32,1.44,-,1.23".to_string();
+        let parsed_output = validate_and_parse_output(&ds_outputs, 5).unwrap();
+        let expected_output = vec![
+            Some(dec("1.22")),
+            Some(dec("1.32")),
+            Some(dec("1.44")),
+            None,
+            Some(dec("1.23")),
+        ];
+        assert_eq!(parsed_output, expected_output);
+
+        // Test invalid case
+        let ds_outputs = "NO_DATA,ERROR".to_string();
+        let parsed_output = validate_and_parse_output(&ds_outputs, 2);
+        assert!(parsed_output.is_err());
+    }
+
+    #[test]
+    fn test_parse_output() {
+        // Test structured case
+        let ds_outputs = "v2;1.22:1700000000:15000, -,1.44:1700000060:0.5";
+        let parsed_output = parse_output(ds_outputs, 3).unwrap();
+        let expected_output = vec![
+            Some(PriceReport {
+                price: dec("1.22"),
+                timestamp: Some(1700000000),
+                volume: Some(dec("15000.0")),
+            }),
+            None,
+            Some(PriceReport {
+                price: dec("1.44"),
+                timestamp: Some(1700000060),
+                volume: Some(dec("0.5")),
+            }),
+        ];
+        assert_eq!(parsed_output, expected_output);
+
+        // Test legacy case
+        let parsed_output = parse_output("1.22,-", 2).unwrap();
+        assert_eq!(
+            parsed_output,
+            vec![Some(PriceReport::from_price(dec("1.22"))), None]
+        );
+
+        // Test invalid structured cases
+        assert!(parse_output("v2;1.22:1700000000:15000", 2).is_err());
+        assert!(parse_output("v2;1.22:1700000000", 1).is_err());
+        assert!(parse_output("v2;-1.22:1700000000:15000", 1).is_err());
+        assert!(parse_output("v2;1.22:1700000000:-1", 1).is_err());
+        assert!(parse_output("v2;1.22:now:15000", 1).is_err());
+        assert!(parse_output("v3;1.22:1700000000:15000", 1).is_err());
+    }
+
+    #[test]
+    fn test_data_source_diagnostics() {
+        let mut diagnostics = DataSourceDiagnostics::new(54);
+        for ds_output in [
+            "1.22,1.32",
+            "v2;1.22:1700000000:15000",
+            "1.22,-0.5,1.44",
+            "v2;1.22:1700000000:-1,-,-",
+            "1.22,abc,1.44",
+            "v2;1.22:now:15000,-,-",
+        ] {
+            diagnostics.record(&parse_output(ds_output, 3).unwrap_err());
+        }
+        assert_eq!(
+            diagnostics,
+            DataSourceDiagnostics {
+                data_source_id: 54,
+                mismatched_length: 2,
+                negative_value: 2,
+                invalid_value: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_timely_report() {
+        let report = PriceReport {
+            price: dec("1.0"),
+            timestamp: Some(1000),
+            volume: None,
+        };
+
+        // Test stale report case
+        assert!(report.is_timely(1000 + MAX_REPORT_AGE));
+        assert!(!report.is_timely(1001 + MAX_REPORT_AGE));
+
+        // Test future report case
+        assert!(report.is_timely(1000 - MAX_CLOCK_SKEW));
+        assert!(!report.is_timely(999 - MAX_CLOCK_SKEW));
+
+        // Reports without a timestamp are always accepted
+        assert!(PriceReport::from_price(dec("1.0")).is_timely(i64::MAX / 2));
+    }
+
+    #[test]
+    fn test_medianize_volumes() {
+        let report = |volume| {
+            Some(PriceReport {
+                price: dec("1.0"),
+                timestamp: Some(0),
+                volume,
+            })
+        };
+        let reports = vec![
+            vec![report(Some(dec("10.0"))), report(None), None],
+            vec![report(Some(dec("20.0"))), report(None), None],
+            vec![report(Some(dec("40.0"))), report(Some(dec("5.0"))), None],
+        ];
+        assert_eq!(
+            medianize_volumes(&reports, 3),
+            vec![Some(dec("20.0")), Some(dec("5.0")), None]
+        );
+    }
+
+    #[test]
+    fn test_get_minimum_response_count() {
+        let min_request = 1..17;
+        let expected_min_responses: Vec<usize> =
+            vec![1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9];
+
+        let min_resp_count = min_request
+            .map(|x| get_minimum_response_count(x as i64))
+            .collect::<Vec<usize>>();
+        assert_eq!(min_resp_count, expected_min_responses);
+    }
+
+    #[test]
+    fn test_outlier_filter() {
+        // Test MAD case
+        let values = vec![dec("1.0"), dec("1.1"), dec("1.2"), dec("1.3"), dec("5.0")];
+        let filtered = OutlierFilter::Mad(3).apply(values, |v| *v);
+        assert_eq!(
+            filtered,
+            vec![dec("1.0"), dec("1.1"), dec("1.2"), dec("1.3")]
+        );
+
+        // Test zero MAD case
+        let values = vec![dec("2.0"), dec("2.0"), dec("2.0"), dec("2.1")];
+        let filtered = OutlierFilter::Mad(3).apply(values, |v| *v);
+        assert_eq!(filtered, vec![dec("2.0"), dec("2.0"), dec("2.0")]);
+
+        // Test band case
+        let values = vec![
+            (dec("90.0"), 1),
+            (dec("100.0"), 1),
+            (dec("105.0"), 1),
+            (dec("111.0"), 1),
+        ];
+        let filtered = OutlierFilter::Band(1000).apply(values, |(v, _)| *v);
+        assert_eq!(
+            filtered,
+            vec![(dec("100.0"), 1), (dec("105.0"), 1), (dec("111.0"), 1)]
+        );
+
+        // Test empty case
+        let filtered = OutlierFilter::Band(1000).apply(Vec::<Decimal>::new(), |v| *v);
+        assert!(filtered.is_empty());
+
+        // Test overflow case
+        let values = vec![Decimal::from_raw(u128::MAX); 3];
+        let filtered = OutlierFilter::Band(1000).apply(values, |v| *v);
+        assert_eq!(filtered.len(), 3);
+    }
+
+    #[test]
+    fn test_filter_and_medianize() {
+        // Test normal case
+        let rates = vec![
+            vec![Some(dec("0.0")), Some(dec("1.3")), Some(dec("2.3"))],
+            vec![Some(dec("0.1")), Some(dec("1.0")), Some(dec("2.0"))],
+            vec![Some(dec("0.3")), Some(dec("1.1")), Some(dec("2.3"))],
+            vec![Some(dec("0.3")), Some(dec("1.1")), Some(dec("2.3"))],
+        ];
+        let result = filter_and_medianize(rates, 3, 2, &DEFAULT_VALIDATOR_OUTLIER_FILTER);
+        let expected_result = vec![Some(dec("0.2")), Some(dec("1.1")), Some(dec("2.3"))];
+        assert_eq!(result, expected_result);
+
+        // Test too many missing case
+        let rates = vec![
+            vec![Some(dec("0.0")), Some(dec("1.3")), None],
+            vec![Some(dec("0.1")), Some(dec("1.0")), None],
+            vec![Some(dec("0.3")), Some(dec("1.1")), None],
+            vec![Some(dec("0.3")), Some(dec("1.1")), Some(dec("2.3"))],
+        ];
+        let result = filter_and_medianize(rates, 3, 2, &DEFAULT_VALIDATOR_OUTLIER_FILTER);
+        let expected_result = vec![Some(dec("0.2")), Some(dec("1.1")), None];
+        assert_eq!(result, expected_result);
+
+        // Test outlier case
+        let rates = vec![
+            vec![Some(dec("1.0"))],
+            vec![Some(dec("1.0"))],
+            vec![Some(dec("1.0"))],
+            vec![Some(dec("100.0"))],
+        ];
+        let result = filter_and_medianize(rates, 1, 2, &DEFAULT_VALIDATOR_OUTLIER_FILTER);
+        assert_eq!(result, vec![Some(dec("1.0"))]);
+
+        // Test too many outliers case
+        let rates = vec![
+            vec![Some(dec("1.0"))],
+            vec![Some(dec("1.0"))],
+            vec![Some(dec("100.0"))],
+        ];
+        let result = filter_and_medianize(rates.clone(), 1, 3, &DEFAULT_VALIDATOR_OUTLIER_FILTER);
+        assert_eq!(result, vec![None]);
+
+        // Test custom outlier filter case
+        let result = filter_and_medianize(rates, 1, 3, &OutlierFilter::Band(1_000_000));
+        assert_eq!(result, vec![Some(dec("1.0"))]);
+    }
+
+    #[test]
+    fn test_weighted_median() {
+        // Test equal weights case
+        let data = vec![
+            (dec("1.23"), 1),
+            (dec("1.27"), 1),
+            (dec("1.25"), 1),
+            (dec("1.24"), 1),
+            (dec("1.26"), 1),
+        ];
+        assert_eq!(weighted_median(data), Some(dec("1.25")));
+
+        // Test equal weights even length case
+        let data = vec![
+            (dec("1.0"), 2),
+            (dec("2.0"), 2),
+            (dec("3.0"), 2),
+            (dec("4.0"), 2),
+        ];
+        assert_eq!(weighted_median(data), Some(dec("2.5")));
+
+        // Test heavy weight case
+        let data = vec![
+            (dec("1.0"), 1),
+            (dec("2.0"), 1),
+            (dec("3.0"), 1),
+            (dec("4.0"), 4),
+        ];
+        assert_eq!(weighted_median(data), Some(dec("4.0")));
+
+        // Test exact half weight case
+        let data = vec![(dec("1.0"), 3), (dec("2.0"), 1), (dec("3.0"), 2)];
+        assert_eq!(weighted_median(data), Some(dec("1.5")));
+
+        // Test empty case
+        assert_eq!(weighted_median(vec![]), None);
+    }
+
+    #[test]
+    fn test_interquartile_range() {
+        // Test equal weights case
+        let data = vec![
+            (dec("1.0"), 1),
+            (dec("2.0"), 1),
+            (dec("3.0"), 1),
+            (dec("4.0"), 1),
+        ];
+        assert_eq!(interquartile_range(data), Some((dec("1.5"), dec("3.5"))));
+
+        // Test weighted case
+        let data = vec![(dec("1.0"), 1), (dec("2.0"), 6), (dec("3.0"), 1)];
+        assert_eq!(interquartile_range(data), Some((dec("2.0"), dec("2.0"))));
+
+        // Test empty case
+        assert_eq!(interquartile_range(vec![]), None);
+    }
+
+    #[test]
+    fn test_aggregation_method() {
+        let data = vec![
+            (dec("1.0"), 1),
+            (dec("2.0"), 1),
+            (dec("3.0"), 2),
+            (dec("4.0"), 1),
+            (dec("100.0"), 1),
+        ];
+
+        assert_eq!(
+            AggregationMethod::Median.aggregate(data.clone()),
+            Some(dec("3.0"))
+        );
+        assert_eq!(
+            AggregationMethod::Mean.aggregate(data.clone()),
+            Some(dec("18.833333333333333333"))
+        );
+        assert_eq!(
+            AggregationMethod::TrimmedMean.aggregate(data),
+            Some(dec("3.0"))
+        );
+
+        // Test empty case
+        assert_eq!(AggregationMethod::Mean.aggregate(vec![]), None);
+        assert_eq!(AggregationMethod::TrimmedMean.aggregate(vec![]), None);
+    }
+
+    #[test]
+    fn test_aggregate_value() {
+        // Test normal case
+        let data = vec![
+            (dec("1.23"), 1),
+            (dec("1.24"), 1),
+            (dec("1.25"), 1),
+            (dec("1.26"), 1),
+            (dec("1.27"), 1),
+        ];
+        let normal_res = aggregate_value(&data, &AggregationParams::new(3));
+        assert_eq!(
+            normal_res.unwrap(),
+            Rate {
+                value: 1250000000,
+                low: 1240000000,
+                high: 1260000000,
+            }
+        );
+
+        // Test weighted case
+        let data = vec![
+            (dec("1.23"), 3),
+            (dec("1.24"), 1),
+            (dec("1.25"), 1),
+            (dec("1.26"), 1),
+            (dec("1.27"), 1),
+        ];
+        let weighted_res = aggregate_value(&data, &AggregationParams::new(3));
+        assert_eq!(weighted_res.unwrap().value, 1240000000);
+
+        // Test outlier case
+        let data = vec![
+            (dec("1.23"), 1),
+            (dec("1.24"), 1),
+            (dec("1.25"), 1),
+            (dec("1.26"), 1),
+            (dec("9.99"), 5),
+        ];
+        let outlier_res = aggregate_value(&data, &AggregationParams::new(3));
+        assert_eq!(outlier_res.unwrap().value, 1245000000);
+
+        // Test too many outliers case
+        let data = vec![
+            (dec("1.0"), 1),
+            (dec("1.0"), 1),
+            (dec("2.0"), 1),
+            (dec("3.0"), 1),
+        ];
+        let outlier_res = aggregate_value(&data, &AggregationParams::new(3));
+        assert_eq!(outlier_res.unwrap_err(), ResponseCode::TooManyOutliers);
+
+        // Test custom outlier filter case
+        let params = AggregationParams {
+            data_source_outlier_filter: OutlierFilter::Band(20_000),
+            ..AggregationParams::new(3)
+        };
+        assert_eq!(aggregate_value(&data, &params).unwrap().value, 1500000000);
+
+        // Test decimals case
+        let data = vec![(dec("1.23"), 1), (dec("1.24"), 1), (dec("1.25"), 1)];
+        let params = AggregationParams {
+            multiplier: 100,
+            ..AggregationParams::new(3)
+        };
+        assert_eq!(aggregate_value(&data, &params).unwrap().value, 124);
+
+        // Test max spread case
+        let params = AggregationParams {
+            max_spread_bps: 200,
+            ..AggregationParams::new(3)
+        };
+        assert_eq!(aggregate_value(&data, &params).unwrap().value, 1240000000);
+        let params = AggregationParams {
+            max_spread_bps: 100,
+            ..AggregationParams::new(3)
+        };
+        assert_eq!(
+            aggregate_value(&data, &params).unwrap_err(),
+            ResponseCode::SpreadTooWide
+        );
+
+        // Test max band case
+        let data = vec![
+            (dec("1.23"), 1),
+            (dec("1.24"), 1),
+            (dec("1.25"), 1),
+            (dec("1.26"), 1),
+            (dec("1.27"), 1),
+        ];
+        let params = AggregationParams {
+            max_band_bps: 200,
+            ..AggregationParams::new(3)
+        };
+        assert_eq!(aggregate_value(&data, &params).unwrap().value, 1250000000);
+        let params = AggregationParams {
+            max_band_bps: 150,
+            ..AggregationParams::new(3)
+        };
+        assert_eq!(
+            aggregate_value(&data, &params).unwrap_err(),
+            ResponseCode::HighDispersion
+        );
+
+        // Test band containing the mean case
+        let data = vec![(dec("1.0"), 1), (dec("1.0"), 1), (dec("1.06"), 1)];
+        let params = AggregationParams {
+            method: AggregationMethod::Mean,
+            ..AggregationParams::new(3)
+        };
+        assert_eq!(
+            aggregate_value(&data, &params).unwrap(),
+            Rate {
+                value: 1020000000,
+                low: 1000000000,
+                high: 1060000000,
+            }
+        );
+
+        // Test overflow case
+        let invalid_data = vec![(Decimal::from_raw(u128::MAX), 1); 5];
+        let overflow_res = aggregate_value(&invalid_data, &AggregationParams::new(3));
+        assert_eq!(overflow_res.unwrap_err(), ResponseCode::ConversionError);
+
+        // Test large price case
+        let data = vec![(dec("12345678901.123456789"), 1); 5];
+        let res = aggregate_value(&data, &AggregationParams::new(3));
+        assert_eq!(res.unwrap().value, 12345678901123456789);
+
+        // Test smallest unit case
+        let data = vec![(dec("1e-9"), 1); 5];
+        let res = aggregate_value(&data, &AggregationParams::new(3));
+        assert_eq!(res.unwrap().value, 1);
+
+        // Test exact mean case, as 1.15 has no exact floating point representation
+        let data = vec![(dec("1.1"), 1), (dec("1.2"), 1)];
+        let params = AggregationParams {
+            method: AggregationMethod::Mean,
+            multiplier: 1_000_000_000_000_000_000,
+            ..AggregationParams::new(2)
+        };
+        assert_eq!(
+            aggregate_value(&data, &params).unwrap().value,
+            1150000000000000000
+        );
+
+        // Test not enough sources case
+        let invalid_data = vec![];
+        let overflow_res = aggregate_value(&invalid_data, &AggregationParams::new(3));
+        assert_eq!(overflow_res.unwrap_err(), ResponseCode::NotEnoughSources);
+    }
+
+    #[test]
+    fn test_symbol_minimum_source_count() {
+        let params = AggregationParams::new(4);
+        assert_eq!(params.for_symbol("ETH").minimum_source_count, 4);
+        assert_eq!(params.for_symbol("WBTC").minimum_source_count, 2);
+        assert_eq!(params.for_symbol("DNE").minimum_source_count, 4);
+
+        // A lower requested minimum is kept as is
+        let params = AggregationParams::new(1);
+        assert_eq!(params.for_symbol("WBTC").minimum_source_count, 1);
+    }
+
+    fn primary_rates(primary: Vec<(Decimal, u64)>) -> SymbolRates {
+        SymbolRates {
+            primary,
+            ..SymbolRates::new(USD)
+        }
+    }
+
+    #[test]
+    fn test_fallback_sources() {
+        let eth = SYMBOLS.get("ETH").unwrap();
+        assert!(!eth.is_fallback(&DataSources::BINANCE));
+        assert!(eth.is_fallback(&DataSources::COINGECKO));
+        assert_eq!(eth.all_sources().count(), 9);
+
+        let symbols = get_symbols_for_data_sources(&["ETH".to_string()]);
+        assert_eq!(symbols.len(), 9);
+        assert_eq!(symbols[&DataSources::COINGECKO], vec!["ETH".to_string()]);
+    }
+
+    #[test]
+    fn test_get_symbols_for_data_sources_order() {
+        let symbols = ["USDT", "ETH", "DNE", "ASTR"].map(String::from);
+        let ds_symbols = get_symbols_for_data_sources(&symbols);
+
+        // Data sources are ordered by name, whatever the order of the symbols
+        assert!(ds_symbols.keys().eq(DataSources::ALL
+            .iter()
+            .filter(|ds| ds_symbols.contains_key(ds))));
+        assert!(ds_symbols
+            .keys()
+            .collect::<Vec<&DataSources>>()
+            .windows(2)
+            .all(|w| w[0].name() < w[1].name()));
+        assert_eq!(ds_symbols.keys().next(), Some(&DataSources::BINANCE));
+
+        // Symbols keep the requested order
+        assert_eq!(
+            ds_symbols[&DataSources::COINGECKO],
+            vec!["USDT".to_string(), "ETH".to_string(), "ASTR".to_string()]
+        );
+        assert_eq!(
+            ds_symbols[&DataSources::KRAKEN],
+            vec!["USDT".to_string(), "ETH".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_symbol_rates_from_source_rates() {
+        let info = SYMBOLS.get("ETH").unwrap();
+        let source_rate = |source, volume| SourceRate {
+            source,
+            rate: dec("1.0"),
+            volume,
+        };
+
+        // Test static weights case
+        let source_rates = vec![
+            source_rate(DataSources::BINANCE, Some(dec("100.0"))),
+            source_rate(DataSources::OKX, None),
+            source_rate(DataSources::COINGECKO, None),
+        ];
+        let rates = SymbolRates::from_source_rates(info, &source_rates);
+        assert_eq!(rates.primary, vec![(dec("1.0"), 3), (dec("1.0"), 2)]);
+        assert_eq!(rates.fallback, vec![(dec("1.0"), 2)]);
+
+        // Test volume weights case
+        let source_rates = vec![
+            source_rate(DataSources::BINANCE, Some(dec("100.0"))),
+            source_rate(DataSources::OKX, Some(dec("50.0"))),
+            source_rate(DataSources::KRAKEN, Some(dec("0.0"))),
+        ];
+        let rates = SymbolRates::from_source_rates(info, &source_rates);
+        assert_eq!(
+            rates.primary,
+            vec![(dec("1.0"), 3000), (dec("1.0"), 1000), (dec("1.0"), 2)]
+        );
+    }
+
+    #[test]
+    fn test_aggregate_symbol_rates() {
+        let params = AggregationParams::new(3);
+
+        // Test enough primary sources case
+        let rates = SymbolRates {
+            quote: USD,
+            primary: vec![(dec("1.0"), 1), (dec("1.1"), 1), (dec("1.2"), 1)],
+            fallback: vec![(dec("5.0"), 1)],
+        };
+        assert_eq!(
+            aggregate_symbol_rates(&rates, &params).unwrap(),
+            (
+                Rate {
+                    value: 1100000000,
+                    low: 1000000000,
+                    high: 1200000000,
+                },
+                ResponseCode::Success
+            )
+        );
+
+        // Test fallback case
+        let rates = SymbolRates {
+            quote: USD,
+            primary: vec![(dec("1.0"), 1), (dec("1.01"), 1)],
+            fallback: vec![(dec("1.02"), 1), (dec("1.03"), 1)],
+        };
+        assert_eq!(
+            aggregate_symbol_rates(&rates, &params).unwrap(),
+            (
+                Rate {
+                    value: 1015000000,
+                    low: 1005000000,
+                    high: 1025000000,
+                },
+                ResponseCode::FallbackSuccess
+            )
+        );
+
+        // Test not enough sources with fallback case
+        let rates = SymbolRates {
+            quote: USD,
+            primary: vec![(dec("1.0"), 1)],
+            fallback: vec![(dec("1.2"), 1)],
+        };
+        assert_eq!(
+            aggregate_symbol_rates(&rates, &params).unwrap_err(),
+            ResponseCode::NotEnoughSources
+        );
+
+        // Test no fallback sources case
+        let rates = primary_rates(vec![(dec("1.0"), 1), (dec("1.1"), 1)]);
+        assert_eq!(
+            aggregate_symbol_rates(&rates, &params).unwrap_err(),
+            ResponseCode::NotEnoughSources
+        );
+    }
+
+    #[test]
+    fn test_parse_pair() {
+        assert_eq!(
+            Pair::parse("ETH"),
+            Pair {
+                base: "ETH".to_string(),
+                quote: "USD".to_string()
+            }
+        );
+        assert_eq!(
+            Pair::parse("STDOT/DOT"),
+            Pair {
+                base: "STDOT".to_string(),
+                quote: "DOT".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_chunk_symbols() {
+        let symbols = (0..120).map(|i| format!("S{i}")).collect::<Vec<String>>();
+
+        // Test symbol count limit case
+        let chunks = chunk_symbols(symbols.clone(), 10, 1000);
+        assert_eq!(chunks.len(), 12);
+        assert!(chunks.iter().all(|c| c.len() == 10));
+        assert_eq!(chunks.concat(), symbols);
+
+        // Test calldata size limit case
+        let chunks = chunk_symbols(symbols.clone(), 100, 64);
+        assert!(chunks.iter().all(|c| c.join(" ").len() <= 64));
+        assert_eq!(
+            chunks[0].join(" "),
+            "S0 S1 S2 S3 S4 S5 S6 S7 S8 S9 S10 S11 S12 S13 S14 S15 S16 S17"
+        );
+        assert_eq!(chunks.concat(), symbols);
+
+        // Test oversized symbol case
+        let symbols = vec!["A".to_string(), "B".repeat(10), "C".to_string()];
+        assert_eq!(
+            chunk_symbols(symbols, 10, 4),
+            vec![
+                vec!["A".to_string()],
+                vec!["B".repeat(10)],
+                vec!["C".to_string()]
+            ]
+        );
+
+        // Test empty case
+        assert!(chunk_symbols(vec![], 10, 100).is_empty());
+    }
+
+    #[test]
+    fn test_get_asks() {
+        let symbols = SYMBOLS
+            .keys()
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>();
+        let asks = get_asks(&symbols, Network::Testnet);
+        for ds in DataSources::ALL {
+            let ds_asks = asks
+                .iter()
+                .filter(|a| a.source == ds)
+                .collect::<Vec<&Ask>>();
+            let ds_symbols = get_symbols_for_data_sources(&symbols).remove(&ds).unwrap();
+            assert_eq!(
+                ds_asks
+                    .iter()
+                    .flat_map(|a| a.symbols.clone())
+                    .collect::<Vec<String>>(),
+                ds_symbols
+            );
+            for (i, ask) in ds_asks.iter().enumerate() {
+                assert!(ask.symbols.len() <= MAX_CHUNK_SYMBOLS);
+                assert!(ask.symbols.join(" ").len() <= MAX_CHUNK_CALLDATA_SIZE);
+                assert_eq!(ask.external_id, external_id(ds.id(Network::Testnet), i));
+            }
+        }
+        // Every data source is asked for all of its symbols at once
+        assert_eq!(asks.len(), DATA_SOURCE_COUNT);
+
+        // Asks are ordered by data source, then by chunk
+        assert!(asks
+            .windows(2)
+            .all(|w| (w[0].source, w[0].external_id) < (w[1].source, w[1].external_id)));
+        assert_eq!(asks, get_asks(&symbols, Network::Testnet));
+
+        // Asking for every supported symbol fits in the raw request budget
+        assert!(asks.len() <= MAX_RAW_REQUEST_COUNT);
+        assert!(get_asks(&symbols, Network::Mainnet).len() <= MAX_RAW_REQUEST_COUNT);
+    }
+
+    #[test]
+    fn test_input_pairs() {
+        let mut request = input(&["ETH", "DOT/ETH", "ETH/USD", "BTC", "DOT/ETH", ""], 3);
+        assert_eq!(
+            request.pairs(),
+            vec![
+                Pair::parse("ETH"),
+                Pair::parse("DOT/ETH"),
+                Pair::parse("BTC"),
+                Pair::parse(""),
+            ]
+        );
+
+        // Test too many symbols case, counting the unique symbols of every pair
+        let symbols = (0..MAX_SYMBOL_COUNT)
+            .map(|i| format!("S{i}"))
+            .collect::<Vec<String>>();
+        request.symbols = symbols
+            .iter()
+            .flat_map(|base| symbols.iter().map(move |quote| format!("{base}/{quote}")))
+            .chain(["ETH/USD".to_string()])
+            .collect();
+        assert!(request.symbols.len() > 100);
+        assert!(request.validate().is_err());
+        request.symbols.retain(|s| s != "ETH/USD");
+        assert!(request.validate().is_ok());
+        request.symbols = (0..120).map(|i| format!("S{i}")).collect();
+        assert!(request.validate().is_err());
+    }
+
+    #[test]
+    fn test_pair_is_valid() {
+        assert!(Pair::parse("ETH").is_valid());
+        assert!(Pair::parse("ETH/BTC").is_valid());
+        assert!(Pair::parse(&"A".repeat(MAX_SYMBOL_LENGTH)).is_valid());
+        assert!(!Pair::parse(&"A".repeat(MAX_SYMBOL_LENGTH + 1)).is_valid());
+        assert!(!Pair::parse("").is_valid());
+        assert!(!Pair::parse("/").is_valid());
+        assert!(!Pair::parse("ETH/").is_valid());
+        assert!(!Pair::parse("/ETH").is_valid());
+    }
+
+    #[test]
+    fn test_get_required_symbols() {
+        let pairs = vec![
+            Pair::parse("ETH"),
+            Pair::parse("DOT/ETH"),
+            Pair::parse("USD/ETH"),
+            Pair::parse("DNE"),
+            Pair::parse("WBTC"),
+            Pair::parse("ETH/"),
+            Pair::parse(&"A".repeat(MAX_SYMBOL_LENGTH + 1)),
+        ];
+        assert_eq!(
+            get_required_symbols(&pairs, false),
+            vec![
+                "ETH".to_string(),
+                "DOT".to_string(),
+                "DNE".to_string(),
+                "WBTC".to_string(),
+            ]
+        );
+
+        // Test cross-checked wrapped symbols case
+        assert_eq!(
+            get_required_symbols(&pairs, true),
+            vec![
+                "ETH".to_string(),
+                "DOT".to_string(),
+                "DNE".to_string(),
+                "WBTC".to_string(),
+                "BTC".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_derived_symbols() {
+        assert_eq!(
+            get_required_symbols(&[Pair::parse("WASTR")], false),
+            vec!["WASTR".to_string(), "ASTR".to_string()]
+        );
+
+        let params = AggregationParams::new(3);
+        let symbol_prices = HashMap::from([
+            (
+                String::from("ASTR"),
+                primary_rates(vec![(dec("0.059"), 1), (dec("0.06"), 1), (dec("0.061"), 1)]),
+            ),
+            (String::from("WASTR"), SymbolRates::new(USD)),
+        ]);
+        let rate = |symbol: &str| get_pair_rate(&Pair::parse(symbol), &symbol_prices, &params);
+        assert_eq!(
+            rate("WASTR").unwrap(),
+            (
+                Rate {
+                    value: 60000000,
+                    low: 59000000,
+                    high: 61000000,
+                },
+                ResponseCode::Success
+            )
+        );
+        assert_eq!(rate("WASTR/ASTR").unwrap().0.value, 1000000000);
+
+        // Test unavailable underlying case
+        let symbol_prices = HashMap::from([
+            (String::from("ASTR"), SymbolRates::new(USD)),
+            (String::from("WASTR"), SymbolRates::new(USD)),
+        ]);
+        assert_eq!(
+            get_pair_rate(&Pair::parse("WASTR"), &symbol_prices, &params).unwrap_err(),
+            ResponseCode::NotEnoughSources
+        );
+    }
+
+    #[test]
+    fn test_symbol_classes() {
+        let params = AggregationParams {
+            check_wrapped: true,
+            ..AggregationParams::new(3)
+        };
+        // Gets the code of a pair whose base has the given rate
+        let code = |pair: &str, rate: &str| {
+            let pair = Pair::parse(pair);
+            let symbol_prices = HashMap::from([
+                (
+                    String::from("BTC"),
+                    primary_rates(vec![(dec("30000"), 1); 3]),
+                ),
+                (
+                    String::from("USDT"),
+                    primary_rates(vec![(dec("1.0"), 1); 3]),
+                ),
+                (pair.base.clone(), primary_rates(vec![(dec(rate), 1); 3])),
+            ]);
+            get_pair_rate(&pair, &symbol_prices, &params).unwrap().1
+        };
+
+        // Test stablecoin case
+        assert_eq!(code("USDT", "0.99"), ResponseCode::Success);
+        assert_eq!(code("USDT", "1.03"), ResponseCode::PegDeviation);
+        assert_eq!(code("USDT", "0.97"), ResponseCode::PegDeviation);
+
+        // Test wrapped case
+        assert_eq!(code("WBTC", "30200"), ResponseCode::Success);
+        assert_eq!(code("WBTC", "29000"), ResponseCode::PegDeviation);
+        assert_eq!(code("WBTC/USDT", "29000"), ResponseCode::PegDeviation);
+
+        // Test unavailable underlying case
+        let symbol_prices = HashMap::from([(
+            String::from("WBTC"),
+            primary_rates(vec![(dec("29000"), 1); 3]),
+        )]);
+        assert_eq!(
+            get_pair_rate(&Pair::parse("WBTC"), &symbol_prices, &params).unwrap(),
+            (
+                Rate::exact(29000000000000),
+                ResponseCode::ReferenceUnavailable
+            )
+        );
+
+        // Test unchecked wrapped case
+        let unchecked = AggregationParams::new(3);
+        assert_eq!(
+            get_pair_rate(&Pair::parse("WBTC"), &symbol_prices, &unchecked).unwrap(),
+            (Rate::exact(29000000000000), ResponseCode::Success)
+        );
+
+        // Test volatile case
+        assert_eq!(code("ETH", "0.5"), ResponseCode::Success);
+
+        // Test code priority case
+        assert_eq!(
+            combine_codes(ResponseCode::FallbackSuccess, ResponseCode::PegDeviation),
+            ResponseCode::PegDeviation
+        );
+        assert_eq!(
+            combine_codes(
+                ResponseCode::FallbackSuccess,
+                ResponseCode::ReferenceUnavailable
+            ),
+            ResponseCode::ReferenceUnavailable
+        );
+        assert_eq!(
+            combine_codes(ResponseCode::Success, ResponseCode::FallbackSuccess),
+            ResponseCode::FallbackSuccess
+        );
+    }
+
+    #[test]
+    fn test_get_pair_rate() {
+        let params = AggregationParams::new(3);
+        let symbol_prices = HashMap::from([
+            (
+                String::from("DOT"),
+                primary_rates(vec![(dec("6.0"), 1), (dec("6.0"), 1), (dec("6.0"), 1)]),
+            ),
+            (
+                String::from("ETH"),
+                primary_rates(vec![
+                    (dec("3000.0"), 1),
+                    (dec("3000.0"), 1),
+                    (dec("3000.0"), 1),
+                ]),
+            ),
+            (
+                String::from("STDOT"),
+                SymbolRates {
+                    quote: "DOT",
+                    primary: vec![(dec("1.5"), 1), (dec("1.5"), 1), (dec("1.5"), 1)],
+                    fallback: vec![],
+                },
+            ),
+            (
+                String::from("FB"),
+                SymbolRates {
+                    quote: USD,
+                    primary: vec![(dec("2.0"), 1), (dec("2.0"), 1)],
+                    fallback: vec![(dec("2.0"), 1)],
+                },
+            ),
+        ]);
+        let rate = |symbol: &str| get_pair_rate(&Pair::parse(symbol), &symbol_prices, &params);
+
+        // Test USD quoted case
+        assert_eq!(
+            rate("DOT").unwrap(),
+            (Rate::exact(6000000000), ResponseCode::Success)
+        );
+
+        // Test native quote case
+        assert_eq!(
+            rate("STDOT/DOT").unwrap(),
+            (Rate::exact(1500000000), ResponseCode::Success)
+        );
+
+        // Test USD rate of a non-USD quoted symbol case
+        assert_eq!(
+            rate("STDOT").unwrap(),
+            (Rate::exact(9000000000), ResponseCode::Success)
+        );
+
+        // Test cross rate case
+        assert_eq!(
+            rate("DOT/ETH").unwrap(),
+            (Rate::exact(2000000), ResponseCode::Success)
+        );
+        assert_eq!(
+            rate("STDOT/ETH").unwrap(),
+            (Rate::exact(3000000), ResponseCode::Success)
+        );
+
+        // Test fallback propagation case
+        assert_eq!(
+            rate("FB/DOT").unwrap(),
+            (Rate::exact(333333333), ResponseCode::FallbackSuccess)
+        );
+
+        // Test unsupported case
+        assert_eq!(
+            rate("DOT/DNE").unwrap_err(),
+            ResponseCode::SymbolNotSupported
+        );
+
+        // Test invalid symbol case
+        assert_eq!(rate("DOT/").unwrap_err(), ResponseCode::InvalidSymbol);
+        assert_eq!(rate("").unwrap_err(), ResponseCode::InvalidSymbol);
+    }
+
+    #[test]
+    fn test_get_diagnostics() {
+        let symbols = vec!["ETH".to_string(), "DOT".to_string()];
+        let source_rates = HashMap::from([(
+            String::from("ETH"),
+            vec![
+                SourceRate {
+                    source: DataSources::OKX,
+                    rate: dec("3000.5"),
+                    volume: None,
+                },
+                SourceRate {
+                    source: DataSources::BINANCE,
+                    rate: dec("3001"),
+                    volume: Some(dec("100")),
+                },
+            ],
+        )]);
+        let diagnostics = get_diagnostics(
+            &symbols,
+            &source_rates,
+            Network::Mainnet,
+            &AggregationParams::new(3),
+        );
+        assert_eq!(
+            diagnostics,
+            vec![
+                SymbolDiagnostics {
+                    symbol: "ETH".to_string(),
+                    sources: vec![
+                        SourceDiagnostics {
+                            data_source_id: 54,
+                            rate: 3001000000000,
+                        },
+                        SourceDiagnostics {
+                            data_source_id: 56,
+                            rate: 3000500000000,
+                        },
+                    ],
+                },
+                SymbolDiagnostics {
+                    symbol: "DOT".to_string(),
+                    sources: vec![],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_responses() {
+        let pairs = vec![
+            Pair::parse("ETH"),
+            Pair::parse("DOT"),
+            Pair::parse("DNE"),
+            Pair::parse("WBTC"),
+            Pair::parse("ETH/WBTC"),
+        ];
+        let symbol_prices = HashMap::from([
+            (
+                String::from("ETH"),
+                primary_rates(vec![
+                    (dec("1.23"), 1),
+                    (dec("1.24"), 1),
+                    (dec("1.25"), 1),
+                    (dec("1.26"), 1),
+                    (dec("1.27"), 1),
+                ]),
+            ),
+            (
+                String::from("DOT"),
+                primary_rates(vec![(dec("2.31"), 1), (dec("2.32"), 1)]),
+            ),
+            (
+                String::from("WBTC"),
+                primary_rates(vec![(dec("2.4"), 1), (dec("2.6"), 1)]),
+            ),
+        ]);
+        let responses = get_responses(&pairs, symbol_prices, &AggregationParams::new(3));
+        assert_eq!(
+            responses,
+            vec![
+                Response::new(
+                    &pairs[0],
+                    ResponseCode::Success,
+                    Rate {
+                        value: 1250000000,
+                        low: 1240000000,
+                        high: 1260000000,
+                    },
+                ),
+                Response::new(&pairs[1], ResponseCode::NotEnoughSources, Rate::default()),
+                Response::new(&pairs[2], ResponseCode::SymbolNotSupported, Rate::default()),
+                Response::new(
+                    &pairs[3],
+                    ResponseCode::Success,
+                    Rate {
+                        value: 2500000000,
+                        low: 2400000000,
+                        high: 2600000000,
+                    },
+                ),
+                Response::new(
+                    &pairs[4],
+                    ResponseCode::Success,
+                    Rate {
+                        value: 500000000,
+                        low: 476923076,
+                        high: 525000000,
+                    },
+                ),
+            ]
+        );
+        assert_eq!(responses[4].symbol, "ETH");
+        assert_eq!(responses[4].quote_symbol, "WBTC");
+    }
+
+    const PREPARE_TIME: i64 = 1700000000;
+
+    fn input(symbols: &[&str], minimum_source_count: u8) -> Input {
+        Input {
+            symbols: symbols.iter().map(|s| s.to_string()).collect(),
+            minimum_source_count,
+            decimals: DEFAULT_DECIMALS,
+            aggregation_method: AggregationMethod::Median as u8,
+            max_spread_bps: 0,
+            include_diagnostics: true,
+            max_band_bps: 0,
+            validator_outlier_filter: DEFAULT_VALIDATOR_OUTLIER_FILTER.kind(),
+            validator_outlier_threshold: DEFAULT_VALIDATOR_OUTLIER_FILTER.threshold(),
+            data_source_outlier_filter: DEFAULT_DATA_SOURCE_OUTLIER_FILTER.kind(),
+            data_source_outlier_threshold: DEFAULT_DATA_SOURCE_OUTLIER_FILTER.threshold(),
+            check_wrapped: false,
+        }
+    }
+
+    /// Runs the execution phase on the OBI-encoded input and returns the OBI-encoded output
+    fn run_execute(input: Input, network: Network) -> Vec<u8> {
+        let calldata = input.try_to_vec().unwrap();
+        execute(Input::try_from_slice(&calldata).unwrap(), network)
+            .try_to_vec()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_prepare() {
+        MockEnv::new(4, 3, PREPARE_TIME).install();
+        prepare(input(&["WBTC", "ASTR", "DNE"], 3), Network::Mainnet);
+        assert_eq!(
+            asked_external_data(),
+            vec![
+                (54, 54, "ASTR".to_string()),
+                (74, 74, "WBTC ASTR".to_string()),
+                (72, 72, "WBTC ASTR".to_string()),
+                (71, 71, "WBTC ASTR".to_string()),
+                (56, 56, "ASTR".to_string()),
+            ]
+        );
+
+        // Test cross-checked wrapped symbols case
+        MockEnv::new(4, 3, PREPARE_TIME).install();
+        let mut checked = input(&["WBTC", "ASTR", "DNE"], 3);
+        checked.check_wrapped = true;
+        prepare(checked, Network::Mainnet);
+        assert_eq!(
+            asked_external_data(),
+            vec![
+                (54, 54, "BTC ASTR".to_string()),
+                (73, 73, "BTC".to_string()),
+                (74, 74, "WBTC BTC ASTR".to_string()),
+                (72, 72, "WBTC BTC ASTR".to_string()),
+                (71, 71, "WBTC BTC ASTR".to_string()),
+                (76, 76, "BTC".to_string()),
+                (59, 59, "BTC".to_string()),
+                (58, 58, "BTC".to_string()),
+                (56, 56, "BTC ASTR".to_string()),
+            ]
+        );
+
+        MockEnv::new(4, 3, PREPARE_TIME).install();
+        prepare(input(&["WASTR"], 3), Network::Testnet);
+        assert_eq!(
+            asked_external_data(),
+            vec![
+                (230, 230, "ASTR".to_string()),
+                (251, 251, "ASTR".to_string()),
+                (236, 236, "ASTR".to_string()),
+                (232, 232, "ASTR".to_string()),
+                (229, 229, "ASTR".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_max_symbol_count() {
+        // A chunk of the longest symbols fits in the calldata size
+        let symbols = vec!["A".repeat(MAX_SYMBOL_LENGTH); MAX_CHUNK_SYMBOLS];
+        assert_eq!(
+            chunk_symbols(symbols, MAX_CHUNK_SYMBOLS, MAX_CHUNK_CALLDATA_SIZE).len(),
+            1
+        );
+
+        // As many unique symbols as the limit fit in the raw request budget
+        assert!(MAX_SYMBOL_COUNT >= SYMBOLS.len());
+        assert!(
+            DATA_SOURCE_COUNT * MAX_SYMBOL_COUNT.div_ceil(MAX_CHUNK_SYMBOLS)
+                <= MAX_RAW_REQUEST_COUNT
+        );
+    }
+
+    #[test]
+    fn test_prepare_and_execute_all_symbols() {
+        // Requests every supported symbol quoted in several symbols, along with an unsupported one
+        let mut symbols = Vec::new();
+        for symbol in SYMBOLS.keys() {
+            symbols.push(symbol.to_string());
+            for quote in ["ETH", "BTC", "USDT", "DOT", "ATOM", "SOL"] {
+                symbols.push(format!("{symbol}/{quote}"));
+            }
+        }
+        symbols.push("DNE".to_string());
+        assert!(symbols.len() > 100);
+        let mut request = input(&[], 3);
+        request.symbols = symbols.clone();
+        request.include_diagnostics = false;
+
+        MockEnv::new(3, 3, PREPARE_TIME).install();
+        prepare(request.clone(), Network::Mainnet);
+        let asked = asked_external_data();
+        assert_eq!(asked.len(), DATA_SOURCE_COUNT);
+        assert!(asked.iter().all(|(eid, did, _)| eid == did));
+
+        // Validators report 1 for stablecoins and 2 for other symbols under each external id
+        let mut env = MockEnv::new(3, 3, PREPARE_TIME);
+        for (eid, _, calldata) in &asked {
+            let report = calldata
+                .split(' ')
+                .map(|s| match SYMBOLS[s].class {
+                    SymbolClass::Stablecoin => "1",
+                    _ => "2",
+                })
+                .collect::<Vec<&str>>()
+                .join(",");
+            env = env.with_reports(*eid, &[Some(&report), Some(&report), Some(&report)]);
+        }
+        env.install();
+
+        let output = execute(request, Network::Mainnet);
+        assert_eq!(output.responses.len(), symbols.len());
+        for (response, symbol) in zip(&output.responses, &symbols) {
+            let pair = Pair::parse(symbol);
+            assert_eq!(
+                (&response.symbol, &response.quote_symbol),
+                (&pair.base, &pair.quote)
+            );
+            if symbol == "DNE" {
+                assert_eq!(
+                    response.response_code,
+                    ResponseCode::SymbolNotSupported as u8
+                );
+            } else {
+                assert_eq!(response.response_code, ResponseCode::Success as u8);
+            }
+        }
+        let rate =
+            |symbol: &str| output.responses[symbols.iter().position(|s| s == symbol).unwrap()].rate;
+        assert_eq!(rate("ETH"), 2000000000);
+        assert_eq!(rate("USDT/ETH"), 500000000);
+        assert_eq!(rate("WASTR/BTC"), 1000000000);
+        assert_eq!(rate("ETH/USDT"), 2000000000);
+    }
+
+    #[test]
+    fn test_execute_duplicate_symbols() {
+        MockEnv::new(4, 3, PREPARE_TIME)
+            .with_reports(74, &[Some("30000"), Some("30010"), Some("30020")])
+            .with_reports(72, &[Some("30000"), Some("30000"), Some("30000")])
+            .install();
+        let mut wbtc = input(&["WBTC", "WBTC/USD", "", "WBTC"], 3);
+        wbtc.include_diagnostics = false;
+        let expected = Output {
+            responses: vec![
+                Response::new(
+                    &Pair::parse("WBTC"),
+                    ResponseCode::Success,
+                    Rate {
+                        value: 30005000000000,
+                        low: 30000000000000,
+                        high: 30010000000000,
+                    },
+                ),
+                Response::new(
+                    &Pair::parse(""),
+                    ResponseCode::InvalidSymbol,
+                    Rate::default(),
+                ),
+            ],
+            diagnostics: vec![],
+            data_source_diagnostics: vec![],
+        };
+        assert_eq!(
+            run_execute(wbtc, Network::Mainnet),
+            expected.try_to_vec().unwrap()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid oracle script input: Too many symbols")]
+    fn test_prepare_too_many_symbols() {
+        MockEnv::new(4, 3, PREPARE_TIME).install();
+        let symbols = (0..=MAX_SYMBOL_COUNT)
+            .map(|i| format!("S{i}"))
+            .collect::<Vec<String>>();
+        let mut input = input(&[], 3);
+        input.symbols = symbols;
+        prepare(input, Network::Mainnet);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid oracle script input: Invalid aggregation method")]
+    fn test_prepare_invalid_input() {
+        MockEnv::new(4, 3, PREPARE_TIME).install();
+        let mut input = input(&["WBTC"], 3);
+        input.aggregation_method = 3;
+        prepare(input, Network::Mainnet);
+    }
+
+    #[test]
+    fn test_execute() {
+        // CRYPTOCOMPARE only reports malformed outputs, while some other validators fail or
+        // report malformed outputs
+        MockEnv::new(4, 3, PREPARE_TIME)
+            .with_reports(
+                74,
+                &[Some("30000"), Some("30010"), Some("abc"), Some("30020")],
+            )
+            .with_reports(72, &[Some("30000\n"), None, Some("1,2,3"), Some("30000")])
+            .with_reports(71, &[Some("-30000"), Some("30000,-"), Some("v2;30000")])
+            .install();
+
+        let pairs = [Pair::parse("WBTC"), Pair::parse("DNE")];
+        let expected = Output {
+            responses: vec![
+                Response::new(
+                    &pairs[0],
+                    ResponseCode::Success,
+                    Rate {
+                        value: 30005000000000,
+                        low: 30000000000000,
+                        high: 30010000000000,
+                    },
+                ),
+                Response::new(&pairs[1], ResponseCode::SymbolNotSupported, Rate::default()),
+            ],
+            diagnostics: vec![
+                SymbolDiagnostics {
+                    symbol: "WBTC".to_string(),
+                    sources: vec![
+                        SourceDiagnostics {
+                            data_source_id: 72,
+                            rate: 30000000000000,
+                        },
+                        SourceDiagnostics {
+                            data_source_id: 74,
+                            rate: 30010000000000,
+                        },
+                    ],
+                },
+                SymbolDiagnostics {
+                    symbol: "DNE".to_string(),
+                    sources: vec![],
+                },
+            ],
+            data_source_diagnostics: vec![
+                DataSourceDiagnostics {
+                    data_source_id: 71,
+                    mismatched_length: 1,
+                    negative_value: 1,
+                    invalid_value: 1,
+                },
+                DataSourceDiagnostics {
+                    data_source_id: 72,
+                    mismatched_length: 1,
+                    negative_value: 0,
+                    invalid_value: 0,
+                },
+                DataSourceDiagnostics {
+                    data_source_id: 74,
+                    mismatched_length: 0,
+                    negative_value: 0,
+                    invalid_value: 1,
+                },
+            ],
+        };
+        assert_eq!(
+            run_execute(input(&["WBTC", "DNE"], 3), Network::Mainnet),
+            expected.try_to_vec().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_execute_missing_sources() {
+        // Test too few validator responses case
+        MockEnv::new(4, 3, PREPARE_TIME)
+            .with_reports(74, &[Some("30000"), Some("30010"), Some("30020")])
+            .with_reports(72, &[Some("30000")])
+            .install();
+        let mut wbtc = input(&["WBTC"], 3);
+        wbtc.include_diagnostics = false;
+        let expected = Output {
+            responses: vec![Response::new(
+                &Pair::parse("WBTC"),
+                ResponseCode::NotEnoughSources,
+                Rate::default(),
+            )],
+            diagnostics: vec![],
+            data_source_diagnostics: vec![],
+        };
+        assert_eq!(
+            run_execute(wbtc, Network::Mainnet),
+            expected.try_to_vec().unwrap()
+        );
+
+        // Test unavailable underlying case
+        MockEnv::new(4, 3, PREPARE_TIME)
+            .with_reports(74, &[Some("30000,-"), Some("30010,-"), Some("30020,-")])
+            .with_reports(72, &[Some("30000,-"), Some("30000,-"), Some("30000,-")])
+            .install();
+        let mut checked = input(&["WBTC"], 3);
+        checked.include_diagnostics = false;
+        checked.check_wrapped = true;
+        let expected = Output {
+            responses: vec![Response::new(
+                &Pair::parse("WBTC"),
+                ResponseCode::ReferenceUnavailable,
+                Rate {
+                    value: 30005000000000,
+                    low: 30000000000000,
+                    high: 30010000000000,
+                },
+            )],
+            diagnostics: vec![],
+            data_source_diagnostics: vec![],
+        };
+        assert_eq!(
+            run_execute(checked, Network::Mainnet),
+            expected.try_to_vec().unwrap()
+        );
+
+        // Test no reports case
+        MockEnv::new(4, 3, PREPARE_TIME).install();
+        let expected = Output {
+            responses: vec![Response::new(
+                &Pair::parse("ETH"),
+                ResponseCode::NotEnoughSources,
+                Rate::default(),
+            )],
+            diagnostics: vec![SymbolDiagnostics {
+                symbol: "ETH".to_string(),
+                sources: vec![],
+            }],
+            data_source_diagnostics: [54, 56, 58, 59, 71, 72, 73, 74, 76]
+                .into_iter()
+                .map(DataSourceDiagnostics::new)
+                .collect(),
+        };
+        assert_eq!(
+            run_execute(input(&["ETH"], 3), Network::Mainnet),
+            expected.try_to_vec().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_execute_structured_reports() {
+        // Stale and future reports are discarded, leaving too few responses for COINGECKO
+        MockEnv::new(3, 3, PREPARE_TIME)
+            .with_reports(
+                251,
+                &[
+                    Some("v2;2.0:1699999900:300"),
+                    Some("v2;2.0:1699000000:300"),
+                    Some("v2;2.0:1700003600:300"),
+                ],
+            )
+            .with_reports(
+                236,
+                &[Some("v2;2.1:1700000000:100"), Some("v2;2.1:1700000000:100")],
+            )
+            .with_reports(232, &[Some("2.2"), Some("2.2")])
+            .install();
+        let mut wbtc = input(&["WBTC"], 2);
+        wbtc.include_diagnostics = false;
+        let expected = Output {
+            responses: vec![Response::new(
+                &Pair::parse("WBTC"),
+                ResponseCode::Success,
+                Rate {
+                    value: 2150000000,
+                    low: 2100000000,
+                    high: 2200000000,
+                },
+            )],
+            diagnostics: vec![],
+            data_source_diagnostics: vec![],
+        };
+        assert_eq!(
+            run_execute(wbtc, Network::Testnet),
+            expected.try_to_vec().unwrap()
+        );
+    }
+}
@@ -0,0 +1,97 @@
+//! In-process stand-in for the oracle environment interface, so that `prepare` and `execute` can
+//! be run in tests
+//!
+//! Each test thread has its own environment, which is set up with [`MockEnv::install`].
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+thread_local! {
+    static ENV: RefCell<MockEnv> = RefCell::new(MockEnv::default());
+}
+
+/// Oracle request parameters and the reports of its validators
+#[derive(Default)]
+pub struct MockEnv {
+    ask_count: i64,
+    min_count: i64,
+    prepare_time: i64,
+    // Reports of each validator by external id, `None` standing for a report with a nonzero status
+    reports: HashMap<i64, Vec<Option<String>>>,
+    asked: Vec<(i64, i64, String)>,
+}
+
+impl MockEnv {
+    pub fn new(ask_count: i64, min_count: i64, prepare_time: i64) -> Self {
+        MockEnv {
+            ask_count,
+            min_count,
+            prepare_time,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the reports of the validators for the given external id
+    ///
+    /// Validators without a report are treated as having failed to run the data source.
+    pub fn with_reports(mut self, eid: i64, reports: &[Option<&str>]) -> Self {
+        self.reports
+            .insert(eid, reports.iter().map(|r| r.map(String::from)).collect());
+        self
+    }
+
+    /// Replaces the environment of the current thread
+    pub fn install(self) {
+        ENV.with(|env| *env.borrow_mut() = self);
+    }
+}
+
+/// Returns the raw requests issued so far as external id, data source id and calldata, in the
+/// order they were issued
+pub fn asked_external_data() -> Vec<(i64, i64, String)> {
+    ENV.with(|env| env.borrow().asked.clone())
+}
+
+/// Mock of `owasm_kit::oei`
+pub mod oei {
+    use super::ENV;
+
+    pub fn get_ask_count() -> i64 {
+        ENV.with(|env| env.borrow().ask_count)
+    }
+
+    pub fn get_min_count() -> i64 {
+        ENV.with(|env| env.borrow().min_count)
+    }
+
+    pub fn get_prepare_time() -> i64 {
+        ENV.with(|env| env.borrow().prepare_time)
+    }
+
+    pub fn ask_external_data(eid: i64, did: i64, calldata: &[u8]) {
+        let calldata = String::from_utf8(calldata.to_vec()).unwrap();
+        ENV.with(|env| env.borrow_mut().asked.push((eid, did, calldata)));
+    }
+
+    pub fn get_external_data(eid: i64, vid: i64) -> Result<String, i64> {
+        ENV.with(|env| {
+            env.borrow()
+                .reports
+                .get(&eid)
+                .and_then(|reports| reports.get(vid as usize).cloned().flatten())
+                .ok_or(1)
+        })
+    }
+}
+
+/// Mock of `owasm_kit::ext`
+pub mod ext {
+    use super::oei;
+    use std::str::FromStr;
+
+    pub fn load_input<T: FromStr>(eid: i64) -> impl Iterator<Item = T> {
+        (0..oei::get_ask_count())
+            .filter_map(move |vid| oei::get_external_data(eid, vid).ok())
+            .filter_map(|r| r.trim_end().parse::<T>().ok())
+    }
+}
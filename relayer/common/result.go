@@ -16,6 +16,20 @@ func ExtractResultFromMsg(jsonBytes []byte) (oracletypes.Result, error) {
 	return r, nil
 }
 
+// DecodeResult decodes an oracle script output, falling back to the legacy USD only layout
+func DecodeResult(data []byte) (Result, error) {
+	var decoded Result
+	if err := obi.Decode(data, &decoded); err == nil {
+		return decoded, nil
+	}
+
+	var legacy LegacyResult
+	if err := obi.Decode(data, &legacy); err != nil {
+		return Result{}, err
+	}
+	return legacy.ToResult(), nil
+}
+
 func TaskFromResult(r oracletypes.Result) (Task, error) {
 	var cd Calldata
 	err := obi.Decode(r.Calldata, &cd)
@@ -23,8 +37,7 @@ func TaskFromResult(r oracletypes.Result) (Task, error) {
 		return Task{}, err
 	}
 
-	var decoded Result
-	err = obi.Decode(r.Result, &decoded)
+	decoded, err := DecodeResult(r.Result)
 	if err != nil {
 		return Task{}, err
 	}
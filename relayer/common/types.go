@@ -91,22 +91,91 @@ func (d Calldata) GetMultiplier() uint64 {
 	return 1000000000
 }
 
+// USD is the quote symbol of the rates stored by the StandardReference contract
+const USD = "USD"
+
+// Response codes of the oracle script whose rates are relayed. FallbackSuccess is returned when
+// the rate was computed with the fallback data sources of the symbol.
+const (
+	ResponseCodeSuccess         uint8 = 0
+	ResponseCodeFallbackSuccess uint8 = 6
+)
+
 type Result struct {
-	Responses []Response
+	Responses             []Response
+	Diagnostics           []SymbolDiagnostics
+	DataSourceDiagnostics []DataSourceDiagnostics
 }
 
 type Response struct {
 	Symbol       string
 	ResponseCode uint8
 	Rate         uint64
+	QuoteSymbol  string
+	RateLow      uint64
+	RateHigh     uint64
+}
+
+// IsSuccess checks whether the oracle script returned a rate which can be relayed
+func (r Response) IsSuccess() bool {
+	return r.ResponseCode == ResponseCodeSuccess || r.ResponseCode == ResponseCodeFallbackSuccess
+}
+
+// SymbolDiagnostics lists the data sources which contributed a rate to a symbol. It is always
+// encoded, but only filled when requested in the calldata.
+type SymbolDiagnostics struct {
+	Symbol  string
+	Sources []SourceDiagnostics
+}
+
+type SourceDiagnostics struct {
+	DataSourceId int64
+	Rate         uint64
+}
+
+// DataSourceDiagnostics counts the validator outputs of a data source rejected for each reason.
+// It is always encoded, but only filled when requested in the calldata.
+type DataSourceDiagnostics struct {
+	DataSourceId     int64
+	MismatchedLength uint32
+	NegativeValue    uint32
+	InvalidValue     uint32
+}
+
+// LegacyResult is the output of oracle scripts which only return USD rates
+type LegacyResult struct {
+	Responses []LegacyResponse
+}
+
+type LegacyResponse struct {
+	Symbol       string
+	ResponseCode uint8
+	Rate         uint64
+}
+
+func (r LegacyResult) ToResult() Result {
+	responses := make([]Response, 0, len(r.Responses))
+	for _, resp := range r.Responses {
+		responses = append(responses, Response{
+			Symbol:       resp.Symbol,
+			ResponseCode: resp.ResponseCode,
+			Rate:         resp.Rate,
+			QuoteSymbol:  USD,
+			RateLow:      resp.Rate,
+			RateHigh:     resp.Rate,
+		})
+	}
+	return Result{Responses: responses}
 }
 
 func (r Result) GetPrices(_ []string) ([]Response, []Response, error) {
 	valids := make([]Response, 0)
 	fails := make([]Response, 0)
 	for _, r := range r.Responses {
-		// Check if response code is non-zero, if it is, add to failedResponses and ignore
-		if r.ResponseCode == 0 {
+		// Check if response code is a success, if it is not, add to failedResponses and ignore.
+		// The contract stores rates by base symbol against USD, so rates of pairs quoted in
+		// another symbol are not relayed either.
+		if r.IsSuccess() && r.QuoteSymbol == USD {
 			valids = append(valids, r)
 		} else {
 			fails = append(fails, r)